@@ -0,0 +1,60 @@
+//! Generates a world without a window and writes it to files, to compare seeds and generator changes.
+//!
+//! Usage: `worldgen <seed> <width>x<height> <params.ron> [output directory]`
+//!
+//! Writes `heightmap.png`, `biomes.png` and `chunks.ron` to the output directory, `worldgen` by default.
+
+use my_game::{Chunk, WorldGenParams, WorldMap, split_map};
+use std::{collections::BTreeMap, env, error::Error, fs, path::PathBuf, process::ExitCode};
+
+const USAGE: &str = "Usage: worldgen <seed> <width>x<height> <params.ron> [output directory]";
+const DEFAULT_OUTPUT_DIR: &str = "worldgen";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let [seed, size, params, rest @ ..] = args else {
+        return Err("Missing arguments".into());
+    };
+    let seed: u32 = seed.parse().map_err(|err| format!("Invalid seed {}: {}", seed, err))?;
+    let size = parse_size(size).ok_or_else(|| format!("Invalid size {}, expected <width>x<height>", size))?;
+    let params = WorldGenParams::load(params).map_err(|err| format!("Unable to load {}: {}", params, err))?;
+    let output_dir = PathBuf::from(rest.first().map_or(DEFAULT_OUTPUT_DIR, String::as_str));
+
+    let default_map = WorldMap::default();
+    let world_map = WorldMap::new(seed, size, default_map.x_bounds, default_map.y_bounds, params);
+    let terrain_map = world_map.generate();
+    // Sorted so the same world always gives the same file
+    let chunks: BTreeMap<(i32, i32), Chunk> = split_map(&terrain_map, &world_map.caves()).into_iter().collect();
+
+    fs::create_dir_all(&output_dir)?;
+    terrain_map
+        .heightmap_image()
+        .try_into_dynamic()?
+        .save(output_dir.join("heightmap.png"))?;
+    terrain_map
+        .biome_image()
+        .try_into_dynamic()?
+        .save(output_dir.join("biomes.png"))?;
+    // One tile per line keeps the dump readable in a diff
+    let ron_chunks = ron::ser::to_string_pretty(&chunks, ron::ser::PrettyConfig::default().depth_limit(5))?;
+    fs::write(output_dir.join("chunks.ron"), ron_chunks)?;
+
+    println!("Generated {} chunks in {}", chunks.len(), output_dir.display());
+    Ok(())
+}
+
+/// Parses a size written as `<width>x<height>`
+fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let (width, height) = size.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
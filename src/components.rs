@@ -1,6 +1,6 @@
-use crate::Position;
-use bevy::prelude::{Component, Entity, Reflect};
-use serde::Deserialize;
+use crate::{Position, forget_entity_id};
+use bevy::prelude::{Component, Entity, LinearRgba, Reflect};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
 #[derive(Deserialize, Component, Debug, Clone, Eq, Hash, PartialEq, Default)]
@@ -15,12 +15,14 @@ pub struct Item {}
 #[derive(Deserialize, Component, Debug, Clone, Eq, Hash, PartialEq, Default)]
 pub struct Consumable {}
 
+/// Identifier of a sprite of the [`SpriteRegistry`](crate::SpriteRegistry)
+#[derive(Deserialize, Reflect, Debug, Clone, Copy, Eq, Hash, PartialEq, Default)]
+pub struct TextureId(pub u32);
+
 #[derive(Deserialize, Component, Debug, Clone, Eq, Hash, PartialEq, Default)]
-pub struct Renderable(
-    pub String, //TODO now is the path. This should be the identifier of the texture
-);
+pub struct Renderable(pub TextureId);
 
-#[derive(Deserialize, Component, Reflect, Debug, Clone, Eq, Hash, PartialEq, Default)]
+#[derive(Serialize, Deserialize, Component, Reflect, Debug, Clone, Eq, Hash, PartialEq, Default)]
 pub struct Health {
     pub current: u32,
     pub max: u32,
@@ -35,6 +37,12 @@ impl Health {
 #[derive(Deserialize, Component, Reflect, Debug, Clone, Eq, Hash, PartialEq)]
 pub struct Chasing(pub Entity);
 
+/// Identifier of a creature or an item that stays the same once saved and loaded, unlike its [`Entity`].
+/// The [`EntityRegistry`](crate::EntityRegistry) maps it back to the entity
+#[derive(Serialize, Deserialize, Component, Reflect, Debug, Clone, Copy, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[component(on_replace = forget_entity_id)]
+pub struct EntityId(pub u64);
+
 #[derive(Deserialize, Component, Debug, Clone, Eq, Hash, PartialEq, Default)]
 pub struct CursorHighlight {}
 
@@ -58,6 +66,13 @@ impl Viewshed {
     }
 }
 
+/// Light given by a creature or an item to the tiles it reaches within `range`
+#[derive(Component, Debug, Reflect, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub range: u32,
+    pub color: LinearRgba,
+}
+
 #[derive(Component, Debug, Reflect, Clone, Eq, PartialEq, Default)]
 pub struct Backpack {
     pub content: HashSet<Entity>,
@@ -90,7 +105,23 @@ pub enum Race {
     BadHuman,
 }
 
+/// Side a creature is on, the creatures of a faction share what they see
+#[derive(Serialize, Deserialize, Component, Debug, Reflect, Clone, Copy, Eq, Hash, PartialEq, Default)]
+pub enum Faction {
+    /// Creatures of the player
+    #[default]
+    Colony,
+    Hostile,
+}
+
 impl Race {
+    pub fn get_faction(&self) -> Faction {
+        match self {
+            Self::Human => Faction::Colony,
+            Self::BadHuman => Faction::Hostile,
+        }
+    }
+
     pub fn get_attributes(&self) -> Attributes {
         match self {
             Self::Human => Attributes::new(10, 10, 10, 10, 10, 10, 10),
@@ -112,7 +143,7 @@ impl Race {
     }
 }
 
-#[derive(Component, Debug, Reflect, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Component, Debug, Reflect, Clone, Eq, PartialEq)]
 pub struct Attributes {
     pub strength: u32,
     pub dexterity: u32,
@@ -3,8 +3,8 @@ use crate::{
     Position,
 };
 use bevy::prelude::{
-    App, Commands, Entity, GlobalTransform, Message, MessageReader, MessageWriter, Name, Plugin, PreUpdate, Query, Res,
-    ResMut, Transform, info,
+    App, Commands, DetectChangesMut, Entity, GlobalTransform, Message, MessageReader, MessageWriter, Name, Plugin,
+    PreUpdate, Query, Res, ResMut, Transform, info, warn,
 };
 use std::ops::Neg;
 
@@ -16,6 +16,8 @@ mod inventory;
 use inventory::*;
 mod healthy;
 use healthy::*;
+mod terrain;
+use terrain::*;
 
 pub struct EffectsPlugin;
 
@@ -31,6 +33,7 @@ impl Plugin for EffectsPlugin {
             .add_message::<Effect<UseItem>>()
             .add_message::<Effect<DropItem>>()
             .add_message::<Effect<EquipItem>>()
+            .add_message::<Effect<ModifyTile>>()
             .add_systems(
                 PreUpdate,
                 (
@@ -44,6 +47,7 @@ impl Plugin for EffectsPlugin {
                     drop_item,
                     equip_item,
                     heal_entity,
+                    modify_tile,
                 ),
             );
     }
@@ -74,3 +78,5 @@ pub struct UseItem {}
 pub struct DropItem {}
 pub struct EquipItem {}
 pub struct Heal(pub u32);
+/// Name of the tile raw put at the target tiles, `None` digs them out
+pub struct ModifyTile(pub Option<String>);
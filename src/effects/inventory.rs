@@ -20,7 +20,7 @@ pub fn pick_up_item(
                 info!("{} don't have backpack", creature_name);
                 continue;
             };
-            let _ = current_map.items.remove(target_pos);
+            let _ = current_map.items.remove(target_pos, target);
             backpack.content.insert(target);
             commands
                 .entity(target)
@@ -0,0 +1,206 @@
+use super::*;
+use crate::{RawMaster, SpawnEntity, SpawnType, TileData, Viewshed, WorldChunks};
+
+pub fn modify_tile(
+    mut event: MessageReader<Effect<ModifyTile>>,
+    mut commands: Commands,
+    mut spawn_event: MessageWriter<SpawnEntity>,
+    mut current_map: ResMut<CurrentMap>,
+    mut world_chunks: ResMut<WorldChunks>,
+    raw_master: Res<RawMaster>,
+    mut viewshed_query: Query<&mut Viewshed>,
+    mut steps_query: Query<&mut PathfindingSteps>,
+) {
+    let world_chunks = &mut *world_chunks;
+    for ev in event.read() {
+        let tiles = match &ev.targets {
+            Targets::Tile { tile } => vec![*tile],
+            Targets::Tiles { tiles } => tiles.clone(),
+            _ => continue,
+        };
+        let new_tile = match &ev.data.0 {
+            Some(name) => {
+                let Some(index) = raw_master.tile_index.get(name) else {
+                    warn!("Tile: {} is not in the raws", name);
+                    continue;
+                };
+                let raw = &raw_master.raws.tiles[*index];
+                Some((name, raw.tile_type(), raw.blocker))
+            }
+            None => None,
+        };
+
+        for pos in tiles {
+            // The chunk keeps the change, so it is streamed back the way it was left
+            let Some(chunk) = world_chunks.chunks.get_mut(&pos.chunk()) else {
+                warn!("Tried to modify the tile at {:?} out of the world", pos);
+                continue;
+            };
+            match new_tile {
+                Some((name, tile_type, _)) => {
+                    chunk.set_tile(&pos, tile_type);
+                    chunk.tile_names.insert(pos, name.clone());
+                }
+                None => {
+                    chunk.remove_tile(&pos);
+                    chunk.tile_names.remove(&pos);
+                }
+            }
+            world_chunks.modified.insert(pos.chunk());
+            if let Some(decorations) = world_chunks.decorations.get_mut(&pos.chunk()) {
+                decorations.retain(|(_, decoration)| *decoration != pos);
+            }
+            if !world_chunks.loaded.contains(&pos.chunk()) {
+                continue;
+            }
+
+            // The tile and the decorations over it are replaced
+            for entity in current_map.tiles.remove_at(&pos) {
+                commands.entity(entity).despawn();
+            }
+            current_map.set_tile(pos, new_tile.map(|(_, tile_type, blocker)| (tile_type, blocker)));
+            if let Some((name, _, _)) = new_tile {
+                spawn_event.write(SpawnEntity {
+                    name: name.clone(),
+                    pos: SpawnType::AtPosition {
+                        x: pos.x,
+                        y: pos.y,
+                        z: pos.z,
+                    },
+                });
+            }
+
+            // The tiles under and behind it were not spawned while it covered them
+            let [left, right] = current_map.layout.rotation.front_steps();
+            for hidden in [pos - Position::new(0, 0, 1), pos - left, pos - right] {
+                let Some(chunk) = world_chunks.chunks.get(&hidden.chunk()) else { continue };
+                let Some(tile_type) = chunk.get_tile(&hidden) else { continue };
+                if !world_chunks.loaded.contains(&hidden.chunk()) || current_map.tiles.contains(&hidden) {
+                    continue;
+                }
+                let tile = TileData { pos: hidden, tile_type };
+                spawn_event.write(SpawnEntity {
+                    name: chunk.tile_name(&tile, &raw_master),
+                    pos: SpawnType::AtPosition {
+                        x: hidden.x,
+                        y: hidden.y,
+                        z: hidden.z,
+                    },
+                });
+            }
+
+            // What the creatures see and their ways through the tile may not be right anymore
+            for mut viewshed in &mut viewshed_query {
+                if viewshed.visible_tiles.contains(&pos) {
+                    viewshed.set_changed();
+                }
+            }
+            for mut steps in &mut steps_query {
+                if steps.contains(&pos) {
+                    steps.clear();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawner::{game_world, spawn_entity};
+    use crate::{Chunk, TileType};
+    use bevy::ecs::message::Messages;
+    use bevy::prelude::{DetectChanges, IntoScheduleConfigs, Mut, Schedule, World};
+
+    fn modify(world: &mut World, tile: Position, name: Option<&str>) {
+        world.resource_mut::<Messages<Effect<ModifyTile>>>().write(Effect {
+            data: ModifyTile(name.map(str::to_string)),
+            creator: None,
+            targets: Targets::Tile { tile },
+        });
+    }
+
+    #[test]
+    fn dug_and_built_tiles_change_the_chunk_and_the_spawned_map() {
+        // A row of floors over the blocks of the layer below, a creature walks along it
+        let floors: Vec<Position> = (0..5).map(|x| Position::new(x, 0, 1)).collect();
+        let coords = floors[0].chunk();
+        let mut chunk = Chunk::empty(coords.0, coords.1);
+        for floor in &floors {
+            let below = *floor - Position::new(0, 0, 1);
+            assert_eq!((floor.chunk(), below.chunk()), (coords, coords));
+            chunk.set_tile(floor, TileType::Floor);
+            chunk.tile_names.insert(*floor, "StoneFloor".to_string());
+            chunk.set_tile(&below, TileType::Block);
+            chunk.tile_names.insert(below, "StoneBlock".to_string());
+        }
+
+        let mut world = game_world();
+        world.resource_scope(|world, raw_master: Mut<RawMaster>| {
+            world.resource_mut::<CurrentMap>().add_chunk(&chunk, &[], &raw_master);
+        });
+        world.init_resource::<Messages<Effect<ModifyTile>>>();
+        let mut world_chunks = WorldChunks::default();
+        world_chunks.chunks.insert(coords, chunk);
+        world_chunks.loaded.insert(coords);
+        world.insert_resource(world_chunks);
+        for floor in &floors {
+            world.resource_mut::<Messages<SpawnEntity>>().write(SpawnEntity {
+                name: "StoneFloor".to_string(),
+                pos: SpawnType::AtPosition {
+                    x: floor.x,
+                    y: floor.y,
+                    z: floor.z,
+                },
+            });
+        }
+        let mut schedule = Schedule::default();
+        schedule.add_systems((modify_tile, spawn_entity).chain());
+        schedule.run(&mut world);
+
+        let (dug, last) = (floors[2], floors[4]);
+        let mut steps = PathfindingSteps::new();
+        steps.create_path(&floors[0], &last, world.resource::<CurrentMap>());
+        assert!(steps.contains(&dug));
+        let seeing = Viewshed {
+            visible_tiles: floors.iter().copied().collect(),
+            range: 8,
+            angle: 360,
+        };
+        let creature = world.spawn((seeing, steps)).id();
+        let elsewhere = world.spawn(Viewshed::default()).id();
+        world.clear_trackers();
+
+        // Dug out, the block under it is spawned again
+        modify(&mut world, dug, None);
+        schedule.run(&mut world);
+        let chunk = &world.resource::<WorldChunks>().chunks[&coords];
+        assert_eq!(chunk.get_tile(&dug), None);
+        assert!(!chunk.tile_names.contains_key(&dug));
+        assert!(world.resource::<WorldChunks>().modified.contains(&coords));
+        let current_map = world.resource::<CurrentMap>();
+        assert!(!current_map.tiles.contains(&dug));
+        assert!(!current_map.tile_types.contains_key(&dug));
+        let below = dug - Position::new(0, 0, 1);
+        assert!(current_map.tiles.contains(&below));
+        assert!(current_map.blocked_coords.contains(&below));
+        assert_eq!(current_map.tile_types.get(&below), Some(&TileType::Block));
+        // What the creature sees and its way through the tile are computed again
+        assert!(world.entity(creature).get_ref::<Viewshed>().unwrap().is_changed());
+        assert!(!world.entity(elsewhere).get_ref::<Viewshed>().unwrap().is_changed());
+        assert!(world.get::<PathfindingSteps>(creature).unwrap().is_empty());
+
+        // Built up again as a wall
+        world.clear_trackers();
+        modify(&mut world, dug, Some("StoneBlock"));
+        schedule.run(&mut world);
+        let chunk = &world.resource::<WorldChunks>().chunks[&coords];
+        assert_eq!(chunk.get_tile(&dug), Some(TileType::Block));
+        assert_eq!(chunk.tile_names.get(&dug).map(String::as_str), Some("StoneBlock"));
+        let current_map = world.resource::<CurrentMap>();
+        assert_eq!(current_map.tiles.get(&dug).len(), 1);
+        assert!(current_map.blocked_coords.contains(&dug));
+        assert_eq!(current_map.tile_types.get(&dug), Some(&TileType::Block));
+        assert!(world.entity(creature).get_ref::<Viewshed>().unwrap().is_changed());
+    }
+}
@@ -1,32 +1,96 @@
-use crate::{Creature, DropItem, Effect, EquipItem, Item, Move, Position, Targets, UseItem};
+use crate::{
+    Creature, CurrentMap, CursorHighlight, DropItem, Effect, EquipItem, FogOfWar, Item, Move, Position, SpawnEntity,
+    SpawnType, Targets, TileFog, UseItem,
+};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 
-pub fn on_click(
-    ev: On<Pointer<Click>>,
+/// Finds the tile drawn on top under the cursor
+#[derive(SystemParam)]
+pub struct CursorTile<'w, 's> {
+    window: Single<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera: Single<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    current_map: Res<'w, CurrentMap>,
+    fog: Res<'w, FogOfWar>,
+    buttons: Query<'w, 's, &'static Interaction>,
+}
+
+impl CursorTile<'_, '_> {
+    /// Tile under the cursor, none when it is out of the window, over a button or over no tile seen
+    pub fn tile(&self) -> Option<Position> {
+        if self.buttons.iter().any(|interaction| *interaction != Interaction::None) {
+            return None;
+        }
+        let (camera, camera_transform) = *self.camera;
+        let world_pos = self
+            .window
+            .cursor_position()
+            .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())?;
+        // The tiles never seen are not drawn
+        let is_visible =
+            |pos: &Position| self.current_map.tiles.contains(pos) && self.fog.fog(pos) != TileFog::Unexplored;
+        self.current_map.layout.world_to_tile(world_pos, is_visible)
+    }
+}
+
+/// Moves the cursor highlight over the tile at `pos`
+pub fn highlight_tile(
+    pos: &Position,
+    commands: &mut Commands,
+    spawn_event: &mut MessageWriter<SpawnEntity>,
+    highlighted_query: &Query<(Entity, &Position), With<CursorHighlight>>,
+) {
+    let mut highlighted = false;
+    for (entity, highlighted_pos) in highlighted_query {
+        if highlighted_pos == pos {
+            highlighted = true;
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    if !highlighted {
+        spawn_event.write(SpawnEntity {
+            name: "SelectedBlock".to_string(),
+            pos: SpawnType::AtPosition {
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+            },
+        });
+    }
+}
+
+/// Orders the creatures to the tile clicked or to use their items
+pub fn click_system(
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor: CursorTile,
     mut event: MessageWriter<Effect<Move>>,
     mut equip_item_event: MessageWriter<Effect<EquipItem>>,
     mut drop_item_event: MessageWriter<Effect<DropItem>>,
     mut use_item_event: MessageWriter<Effect<UseItem>>,
-    pos: Query<&Position>,
     sword_query: Query<(Entity, &Name), With<Item>>,
     mut creature_query: Query<(Entity, &Name), With<Creature>>,
 ) {
-    let Ok(destination) = pos.get(ev.event_target()) else {
+    let Some(button) = mouse.get_just_pressed().next() else {
+        return;
+    };
+    let Some(destination) = cursor.tile() else {
         return;
     };
-    match ev.button {
-        PointerButton::Primary => {
+    match button {
+        MouseButton::Left => {
             for (entity, name) in creature_query.iter_mut() {
                 if name.as_str() == "Dummy" {
                     event.write(Effect::<Move> {
                         data: Move {},
                         creator: Some(entity),
-                        targets: Targets::Tile { tile: *destination },
+                        targets: Targets::Tile { tile: destination },
                     });
                 }
             }
         }
-        PointerButton::Secondary => {
+        MouseButton::Right => {
             // TODO check first if its in the inventory
             for (entity, name) in creature_query.iter_mut() {
                 if name.as_str() == "Dummy" {
@@ -42,7 +106,7 @@ pub fn on_click(
                 }
             }
         }
-        PointerButton::Middle => {
+        MouseButton::Middle => {
             // TODO check first if its in the inventory
             for (entity, name) in creature_query.iter_mut() {
                 if name.as_str() == "Dummy" {
@@ -63,5 +127,6 @@ pub fn on_click(
                 }
             }
         }
+        _ => {}
     }
 }
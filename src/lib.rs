@@ -12,10 +12,14 @@ mod map;
 mod menus;
 mod raws;
 mod resources;
+mod save;
 mod spawner;
 mod splash;
 mod systems;
 pub use ai::*;
+// The world generation is also used by the worldgen binary
+pub use map::{Chunk, TerrainMap, split_map};
+pub use resources::{WorldGenParams, WorldMap};
 
 pub(crate) use components::*;
 pub(crate) use effects::*;
@@ -32,6 +36,7 @@ use map::WorldCreationPlugin;
 use menus::MenuPlugin;
 use raws::RawsPlugin;
 use resources::ResourcesPlugin;
+use save::SavePlugin;
 use spawner::SpawnerPlugin;
 use splash::SplashPlugin;
 use systems::SystemsPlugin;
@@ -60,6 +65,7 @@ impl Plugin for AppPlugin {
             .add_plugins(EffectsPlugin)
             .add_plugins(WorldCreationPlugin)
             .add_plugins(GamePlugin)
+            .add_plugins(SavePlugin)
             // Reflect
             .register_type::<Position>()
             .register_type::<Viewshed>()
@@ -69,8 +75,10 @@ impl Plugin for AppPlugin {
             .register_type::<InBackpack>()
             .register_type::<Equipment>()
             .register_type::<EquippedBy>()
+            .register_type::<EntityId>()
             .register_type::<Attributes>()
             .register_type::<Race>()
+            .register_type::<Faction>()
             .register_type::<Health>();
 
         #[cfg(debug_assertions)]
@@ -1,7 +1,14 @@
 // TODO this is part of the screens for generating map should be moved out
+mod export;
 mod generation;
+mod import;
+mod preview;
 mod settings;
 
+mod biomes;
+pub use biomes::*;
+mod chunk_meshes;
+mod cutaway;
 mod chunks;
 pub use chunks::*;
 mod layers;
@@ -9,25 +16,44 @@ pub(crate) use layers::TileData;
 mod layout;
 pub(crate) use layout::TILE_SIZE;
 mod tiletype;
-pub use layout::Layout;
-pub(crate) use tiletype::TileType;
+pub use layout::{Layout, ViewRotation};
+pub(crate) use tiletype::{Orientation, TileType, tile_opaque, tile_walk_cost, tile_walkable};
 mod matrix;
+mod placement;
 mod position;
 pub(crate) use position::*;
+mod rotation;
+mod streaming;
+mod tmx;
+pub use tmx::*;
 
 use crate::{GameState, WorldCreationState};
 use bevy::prelude::*;
 
+use chunk_meshes::ChunkMeshPlugin;
+use cutaway::CutawayPlugin;
+use export::MapExportPlugin;
 use generation::MapGenerationPlugin;
+use import::MapImportPlugin;
 use layers::Layer;
+use preview::MapPreviewPlugin;
+use rotation::ViewRotationPlugin;
 use settings::MapSettingsPlugin;
+use streaming::ChunkStreamingPlugin;
 
 pub struct WorldCreationPlugin;
 
 impl Plugin for WorldCreationPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(MapSettingsPlugin)
+            .add_plugins(MapPreviewPlugin)
             .add_plugins(MapGenerationPlugin)
+            .add_plugins(MapImportPlugin)
+            .add_plugins(MapExportPlugin)
+            .add_plugins(ChunkStreamingPlugin)
+            .add_plugins(ChunkMeshPlugin)
+            .add_plugins(ViewRotationPlugin)
+            .add_plugins(CutawayPlugin)
             .add_systems(OnEnter(GameState::InMapCreation), map_setup);
     }
 }
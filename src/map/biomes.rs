@@ -0,0 +1,191 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{Image, ImageSampler};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use noise::utils::NoiseMap;
+use serde::{Deserialize, Serialize};
+
+/// Kind of land of a column of the world, it decides the materials of its tiles
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Biome {
+    /// Under the sea level
+    #[default]
+    Ocean,
+    /// Lowlands next to the sea
+    Beach,
+    /// Bed of a river flowing through the land
+    River,
+    /// Hot and dry
+    Desert,
+    /// Cold
+    Tundra,
+    /// Wet and not too cold
+    Swamp,
+    /// Mild and wet
+    Forest,
+    /// Mild and dry
+    Plains,
+    /// Rough terrain of the badlands terrain group
+    Badlands,
+    /// Rough terrain of the hilly terrain group
+    Hills,
+    /// Rough terrain of the mountainous terrain group
+    Mountains,
+}
+
+impl Biome {
+    /// Tells if it is the rough terrain of a terrain group, it is climbed by stairs instead of ramps
+    #[must_use]
+    pub fn is_rough(self) -> bool {
+        matches!(self, Biome::Badlands | Biome::Hills | Biome::Mountains)
+    }
+
+    /// Color of the biome in the maps of the world
+    #[must_use]
+    pub fn color(self) -> [u8; 3] {
+        match self {
+            Biome::Ocean => [30, 70, 160],
+            Biome::Beach => [220, 200, 140],
+            Biome::River => [60, 120, 200],
+            Biome::Desert => [230, 190, 100],
+            Biome::Tundra => [200, 210, 210],
+            Biome::Swamp => [80, 100, 60],
+            Biome::Forest => [40, 120, 50],
+            Biome::Plains => [120, 180, 80],
+            Biome::Badlands => [190, 110, 60],
+            Biome::Hills => [110, 140, 70],
+            Biome::Mountains => [130, 125, 120],
+        }
+    }
+
+    /// Classifies a point of land by its climate, both values go from -1.0 to 1.0
+    #[must_use]
+    pub fn from_climate(temperature: f64, moisture: f64) -> Self {
+        match (temperature, moisture) {
+            (..-0.3, _) => Biome::Tundra,
+            (0.3.., ..0.0) => Biome::Desert,
+            (_, 0.4..) => Biome::Swamp,
+            (_, 0.0..) => Biome::Forest,
+            _ => Biome::Plains,
+        }
+    }
+}
+
+/// A map of biomes matching the values of a [`NoiseMap`]
+#[derive(Debug, Clone, Default)]
+pub struct BiomeMap {
+    size: (usize, usize),
+    biomes: Vec<Biome>,
+}
+
+impl BiomeMap {
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            size: (width, height),
+            biomes: vec![Biome::default(); width * height],
+        }
+    }
+
+    pub fn set_value(&mut self, x: usize, y: usize, biome: Biome) {
+        if x < self.size.0 && y < self.size.1 {
+            self.biomes[x + y * self.size.0] = biome;
+        }
+    }
+
+    /// Gets the biome at the given coordinates, out of the bounds it is the default one
+    #[must_use]
+    pub fn get_value(&self, x: usize, y: usize) -> Biome {
+        if x < self.size.0 && y < self.size.1 {
+            self.biomes[x + y * self.size.0]
+        } else {
+            Biome::default()
+        }
+    }
+}
+
+/// Values generated around the piece of the world of a [`TerrainMap`] on each side, so the terrain
+/// at its borders can look at the columns next to it
+pub const TERRAIN_MARGIN: usize = 1;
+
+/// Elevations and biomes of the same piece of the world, with [`TERRAIN_MARGIN`] values around it
+pub struct TerrainMap {
+    pub elevations: NoiseMap,
+    pub biomes: BiomeMap,
+    /// Elevation of the sea, the oceans are filled with water up to it
+    pub sea_level: f64,
+}
+
+impl TerrainMap {
+    /// Size of the piece of the world, without the margin
+    #[must_use]
+    pub fn size(&self) -> (usize, usize) {
+        let (width, height) = self.elevations.size();
+        (width - 2 * TERRAIN_MARGIN, height - 2 * TERRAIN_MARGIN)
+    }
+
+    /// Elevation at `(x, y)` of the piece of the world, the margin goes from `-TERRAIN_MARGIN` to
+    /// `TERRAIN_MARGIN` values past the size. `None` out of it
+    #[must_use]
+    pub fn elevation(&self, x: i32, y: i32) -> Option<f64> {
+        let margin = TERRAIN_MARGIN as i32;
+        let (x, y) = (usize::try_from(x + margin).ok()?, usize::try_from(y + margin).ok()?);
+        let (width, height) = self.elevations.size();
+        (x < width && y < height).then(|| self.elevations.get_value(x, y))
+    }
+
+    /// Biome at `(x, y)` of the piece of the world
+    #[must_use]
+    pub fn biome(&self, x: usize, y: usize) -> Biome {
+        self.biomes.get_value(x + TERRAIN_MARGIN, y + TERRAIN_MARGIN)
+    }
+
+    /// Paints the elevations in gray, from black at -1.0 to white at 1.0, the first row is the top of the map
+    #[must_use]
+    pub fn heightmap_image(&self) -> Image {
+        let (width, height) = self.size();
+        let data = (0..height)
+            .rev()
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let elevation = self.elevation(x as i32, y as i32).unwrap_or_default();
+                ((elevation.clamp(-1.0, 1.0) * 0.5 + 0.5) * 255.0) as u8
+            })
+            .collect();
+        map_image((width, height), data, TextureFormat::R8Unorm)
+    }
+
+    /// Paints the biomes shaded by their elevation, the first row is the top of the map
+    #[must_use]
+    pub fn biome_image(&self) -> Image {
+        let (width, height) = self.size();
+        let mut data = Vec::with_capacity(width * height * 4);
+        for y in (0..height).rev() {
+            for x in 0..width {
+                let elevation = self.elevation(x as i32, y as i32).unwrap_or_default().clamp(-1.0, 1.0);
+                let [r, g, b] = self.biome(x, y).color();
+                // The sea is darker the deeper it is and the land brighter the higher it is
+                let shade = (0.75 + elevation * 0.5).clamp(0.25, 1.25);
+                let shaded = |channel: u8| (f64::from(channel) * shade).min(255.0) as u8;
+                data.extend([shaded(r), shaded(g), shaded(b), 255]);
+            }
+        }
+        map_image((width, height), data, TextureFormat::Rgba8UnormSrgb)
+    }
+}
+
+/// Image of a map with one pixel per value, kept in the main world so it can also be saved
+fn map_image((width, height): (usize, usize), data: Vec<u8>, format: TextureFormat) -> Image {
+    let mut image = Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
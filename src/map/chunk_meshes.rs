@@ -0,0 +1,333 @@
+// This lint usually gives bad advice in the context of Bevy -- hiding complex queries behind
+// type aliases tends to obfuscate code while offering no improvement in code cleanliness.
+#![allow(clippy::type_complexity)]
+
+use super::rotation::ViewRotated;
+use super::*;
+use crate::{
+    CurrentMap, CursorHighlight, FogChanged, FogOfWar, GameState, LightChanged, LightMap, RawMaster, Renderable,
+    SpriteRegistry, TILES_ATLAS, Tile, TileFog, ViewshedHighlight,
+};
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use bevy::sprite_render::AlphaMode2d;
+use std::collections::{HashMap, HashSet};
+use std::ops::Neg;
+
+/// Chunk coordinates and layer of a mesh
+type LayerKey = (i32, i32, i32);
+
+pub struct ChunkMeshPlugin;
+
+impl Plugin for ChunkMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TileMaterial>()
+            .init_resource::<ChunkMeshes>()
+            .add_systems(
+                Update,
+                (
+                    create_tile_material,
+                    fog_dirty_layers,
+                    light_dirty_layers,
+                    rotated_dirty_layers,
+                    update_chunk_meshes,
+                    cut_away_layers,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(OnExit(GameState::InGame), clear_chunk_meshes);
+    }
+}
+
+/// Place of a sprite in the tiles atlas of the [`SpriteRegistry`]
+#[derive(Debug, Clone, Copy)]
+struct AtlasSprite {
+    /// Size in world/pixel coordinates
+    size: Vec2,
+    /// Corners in the atlas image, from 0.0 to 1.0
+    uv: Rect,
+}
+
+impl AtlasSprite {
+    fn of(renderable: &Renderable, sprite_registry: &SpriteRegistry) -> Option<Self> {
+        let (size, uv) = sprite_registry.atlas_rect(renderable.0)?;
+        Some(Self { size, uv })
+    }
+}
+
+/// Tint of the tiles the faction shown remembers but does not see right now, whatever their light is
+const REMEMBERED_TINT: LinearRgba = LinearRgba::rgb(0.3, 0.3, 0.4);
+
+/// Material shared by the meshes of every chunk layer, once the tile sprites are packed
+#[derive(Resource, Default)]
+struct TileMaterial(Option<Handle<ColorMaterial>>);
+
+/// Spawned mesh of each chunk layer and the tiles drawn in it
+#[derive(Resource, Default)]
+struct ChunkMeshes {
+    meshes: HashMap<LayerKey, Entity>,
+    /// Layer of each tile entity drawn, to know which mesh to rebuild once it is despawned
+    drawn: HashMap<Entity, LayerKey>,
+    /// Layers whose tiles changed since their mesh was built
+    dirty: HashSet<LayerKey>,
+}
+
+fn create_tile_material(
+    mut tile_material: ResMut<TileMaterial>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    sprite_registry: Res<SpriteRegistry>,
+) {
+    if tile_material.0.is_some() {
+        return;
+    }
+    let Some(atlas) = sprite_registry.atlas_image(TILES_ATLAS) else { return };
+    // Only the opaque pixels are drawn, they write their depth so the tiles of different layers
+    // and chunks are drawn in the same order than the sprites over them
+    tile_material.0 = Some(materials.add(ColorMaterial {
+        texture: Some(atlas.clone()),
+        alpha_mode: AlphaMode2d::Mask(0.5),
+        ..default()
+    }));
+}
+
+/// Marks the layers of the tiles that came into or went out of sight, to draw them with their fog
+fn fog_dirty_layers(mut fog_changed: MessageReader<FogChanged>, mut chunk_meshes: ResMut<ChunkMeshes>) {
+    for FogChanged(positions) in fog_changed.read() {
+        for pos in positions {
+            let (x, y) = pos.chunk();
+            chunk_meshes.dirty.insert((x, y, pos.z));
+        }
+    }
+}
+
+/// Marks the layers of the tiles whose light changed, every layer once the sky does
+fn light_dirty_layers(mut light_changed: MessageReader<LightChanged>, mut chunk_meshes: ResMut<ChunkMeshes>) {
+    let chunk_meshes = &mut *chunk_meshes;
+    for changed in light_changed.read() {
+        match changed {
+            LightChanged::Ambient => chunk_meshes.dirty.extend(chunk_meshes.meshes.keys()),
+            LightChanged::Tiles(positions) => {
+                for pos in positions {
+                    let (x, y) = pos.chunk();
+                    chunk_meshes.dirty.insert((x, y, pos.z));
+                }
+            }
+        }
+    }
+}
+
+/// Marks every layer once the view is turned, their tiles are drawn elsewhere and the ramps and
+/// stairs with the sprites of the new side
+fn rotated_dirty_layers(
+    mut rotated: MessageReader<ViewRotated>,
+    current_map: Res<CurrentMap>,
+    raw_master: Res<RawMaster>,
+    sprite_registry: Res<SpriteRegistry>,
+    mut renderables_query: Query<
+        (&Name, &mut Renderable),
+        (With<Tile>, Without<CursorHighlight>, Without<ViewshedHighlight>),
+    >,
+    mut chunk_meshes: ResMut<ChunkMeshes>,
+) {
+    if rotated.read().count() == 0 {
+        return;
+    }
+    for (name, mut renderable) in &mut renderables_query {
+        let Some(index) = raw_master.tile_index.get(name.as_str()) else { continue };
+        let sprite = raw_master.raws.tiles[*index].sprite_for(current_map.layout.rotation);
+        if let Some(id) = sprite_registry.id(sprite) {
+            renderable.set_if_neq(Renderable(id));
+        }
+    }
+    let chunk_meshes = &mut *chunk_meshes;
+    chunk_meshes.dirty.extend(chunk_meshes.meshes.keys());
+}
+
+/// Rebuilds the meshes of the chunk layers whose tiles were spawned, despawned or changed their fog or
+/// their light
+fn update_chunk_meshes(
+    mut commands: Commands,
+    mut chunk_meshes: ResMut<ChunkMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tile_material: Res<TileMaterial>,
+    sprite_registry: Res<SpriteRegistry>,
+    current_map: Res<CurrentMap>,
+    fog: Res<FogOfWar>,
+    light_map: Res<LightMap>,
+    added_query: Query<(Entity, &Position), (Added<Tile>, Without<CursorHighlight>, Without<ViewshedHighlight>)>,
+    mut removed: RemovedComponents<Tile>,
+    renderables_query: Query<&Renderable, With<Tile>>,
+) {
+    let chunk_meshes = &mut *chunk_meshes;
+    for (entity, pos) in &added_query {
+        let (x, y) = pos.chunk();
+        chunk_meshes.drawn.insert(entity, (x, y, pos.z));
+        chunk_meshes.dirty.insert((x, y, pos.z));
+    }
+    for entity in removed.read() {
+        if let Some(key) = chunk_meshes.drawn.remove(&entity) {
+            chunk_meshes.dirty.insert(key);
+        }
+    }
+    let Some(material) = &tile_material.0 else { return };
+    if chunk_meshes.dirty.is_empty() {
+        return;
+    }
+
+    // The tiles of a chunk are gathered once for all its changed layers
+    let dirty_chunks: HashSet<(i32, i32)> = chunk_meshes.dirty.iter().map(|(x, y, _)| (*x, *y)).collect();
+    let mut layers: HashMap<LayerKey, Vec<(Position, AtlasSprite, LinearRgba)>> = HashMap::new();
+    for chunk in dirty_chunks {
+        for (pos, entity) in current_map.tiles.in_chunk(chunk) {
+            let key = (chunk.0, chunk.1, pos.z);
+            if !chunk_meshes.dirty.contains(&key) {
+                continue;
+            }
+            let tint = match fog.fog(&pos) {
+                TileFog::Unexplored => continue,
+                TileFog::Remembered => REMEMBERED_TINT,
+                TileFog::Visible => light_map.light(&pos),
+            };
+            let Some(sprite) = renderables_query
+                .get(entity)
+                .ok()
+                .and_then(|renderable| AtlasSprite::of(renderable, &sprite_registry))
+            else {
+                continue;
+            };
+            layers.entry(key).or_default().push((pos, sprite, tint));
+        }
+    }
+
+    for key in chunk_meshes.dirty.drain() {
+        let mesh_entity = chunk_meshes.meshes.remove(&key);
+        let Some(tiles) = layers.get(&key) else {
+            if let Some(entity) = mesh_entity {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        let mesh = Mesh2d(meshes.add(layer_mesh(&current_map.layout, tiles)));
+        let entity = match mesh_entity {
+            Some(entity) => {
+                commands.entity(entity).insert(mesh);
+                entity
+            }
+            None => commands
+                .spawn((
+                    Name::new(format!("ChunkLayer {} {} {}", key.0, key.1, key.2)),
+                    mesh,
+                    MeshMaterial2d(material.clone()),
+                    Transform::default(),
+                    layer_visibility(&current_map.layout, key.2),
+                    DespawnOnExit(GameState::InGame),
+                ))
+                .id(),
+        };
+        chunk_meshes.meshes.insert(key, entity);
+    }
+}
+
+/// Hides the meshes of the layers above the level the view is cut at, once it changes
+fn cut_away_layers(
+    chunk_meshes: Res<ChunkMeshes>,
+    current_map: Res<CurrentMap>,
+    mut visibility_query: Query<&mut Visibility>,
+    mut drawn_level: Local<Option<i32>>,
+) {
+    if *drawn_level == current_map.layout.view_level {
+        return;
+    }
+    *drawn_level = current_map.layout.view_level;
+    for (&(_, _, z), &entity) in &chunk_meshes.meshes {
+        if let Ok(mut visibility) = visibility_query.get_mut(entity) {
+            visibility.set_if_neq(layer_visibility(&current_map.layout, z));
+        }
+    }
+}
+
+fn layer_visibility(layout: &Layout, z: i32) -> Visibility {
+    if layout.is_cut_away(z) { Visibility::Hidden } else { Visibility::Inherited }
+}
+
+fn clear_chunk_meshes(mut chunk_meshes: ResMut<ChunkMeshes>) {
+    *chunk_meshes = ChunkMeshes::default();
+}
+
+/// Where the tile at `pos` is drawn, the tiles lower in the screen and in upper layers are drawn
+/// over the others
+fn tile_translation(layout: &Layout, pos: Position) -> Vec3 {
+    let coord = layout.tile_to_world_pos(pos);
+    Vec3::new(coord.x, coord.y, coord.y.neg() / 100.0 + coord.z)
+}
+
+/// Mesh with a quad for each of the `tiles`, tinted by their color, in the order they are given so
+/// the decorations are drawn over the tile sharing their position
+fn layer_mesh(layout: &Layout, tiles: &[(Position, AtlasSprite, LinearRgba)]) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(tiles.len() * 4);
+    let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(tiles.len() * 4);
+    let mut colors: Vec<[f32; 4]> = Vec::with_capacity(tiles.len() * 4);
+    let mut indices: Vec<u32> = Vec::with_capacity(tiles.len() * 6);
+    for (pos, sprite, tint) in tiles {
+        let center = tile_translation(layout, *pos);
+        let half = sprite.size / 2.0;
+        let first = positions.len() as u32;
+        positions.extend([
+            [center.x - half.x, center.y - half.y, center.z],
+            [center.x + half.x, center.y - half.y, center.z],
+            [center.x + half.x, center.y + half.y, center.z],
+            [center.x - half.x, center.y + half.y, center.z],
+        ]);
+        // The image rows go downwards
+        uvs.extend([
+            [sprite.uv.min.x, sprite.uv.max.y],
+            [sprite.uv.max.x, sprite.uv.max.y],
+            [sprite.uv.max.x, sprite.uv.min.y],
+            [sprite.uv.min.x, sprite.uv.min.y],
+        ]);
+        colors.extend([tint.to_f32_array(); 4]);
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::mesh::VertexAttributeValues;
+
+    #[test]
+    fn quads_are_drawn_at_the_depth_of_their_tile() {
+        let layout = Layout::default();
+        let sprite = AtlasSprite {
+            size: TILE_SIZE,
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+        };
+        let tiles = [
+            (Position::new(3, -2, 7), sprite, LinearRgba::WHITE),
+            (Position::new(4, -2, 7), sprite, REMEMBERED_TINT),
+        ];
+        let mesh = layer_mesh(&layout, &tiles);
+        let Some(positions) = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|p| p.as_float3()) else {
+            panic!("expected the mesh to have positions");
+        };
+        assert_eq!(positions.len(), 8);
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR) else {
+            panic!("expected the mesh to have colors");
+        };
+        for (i, (pos, _, tint)) in tiles.iter().enumerate() {
+            assert_eq!(colors[i * 4], tint.to_f32_array());
+            let coord = layout.tile_to_world_pos(*pos);
+            let depth = coord.y.neg() / 100.0 + coord.z;
+            assert!(positions[i * 4..i * 4 + 4].iter().all(|vertex| vertex[2] == depth));
+        }
+    }
+}
@@ -1,14 +1,21 @@
-use super::Layer;
-use crate::{Position, TileData, TileType};
+use super::{Biome, Layer, TerrainMap, ViewRotation};
+use crate::{Orientation, Position, RawMaster, TileData, TileType};
 use bevy::prelude::Component;
-use noise::utils::NoiseMap;
-use std::collections::HashMap;
+use noise::NoiseFn;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 /// (columns, rows, layers)
-pub const CHUNK_DIMENSIONS: (i32, i32, i32) = (16, 16, 1);
+pub const CHUNK_DIMENSIONS: (i32, i32, i32) = (16, 16, 32);
+
+/// Layers of solid ground under the lowest point of the terrain, left for digging
+pub const UNDERGROUND_LAYERS: i32 = 16;
+
+/// Value of the caves noise over which the ground is carved
+const CAVES_THRESHOLD: f64 = 0.3;
 
 /// Chunk parameters.
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Chunk {
     /// x coordinate of the chunk
     pub x: i32,
@@ -16,46 +23,258 @@ pub struct Chunk {
     pub y: i32,
     /// Iterator of the layers of the 'Chunk'
     pub layers: Vec<Layer>,
+    /// Terrain of each column, in the same order than the tiles of a full layer
+    pub columns: Vec<Column>,
+    /// Tiles spawned with the given name instead of the one of their biome, like the ones of
+    /// hand-authored maps
+    #[serde(default)]
+    pub tile_names: HashMap<Position, String>,
+}
+
+/// Terrain data shared by every tile of a column of a [`Chunk`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Column {
+    pub biome: Biome,
+    /// Layer of the terrain surface before carving the caves
+    pub elevation: i32,
 }
 
 impl Chunk {
     /// Generates a new chunk with given x and y coordinates
     #[must_use]
     pub fn new(x: i32, y: i32) -> Self {
-        let layers = (0..CHUNK_DIMENSIONS.2).map(|z| Layer::new(x, y, z)).collect();
-        Self { x, y, layers }
+        let layers = (0..CHUNK_DIMENSIONS.2)
+            .map(|z| Layer::new(z, Some(TileType::Block)))
+            .collect();
+        let columns = vec![Column::default(); (CHUNK_DIMENSIONS.0 * CHUNK_DIMENSIONS.1) as usize];
+        Self {
+            x,
+            y,
+            layers,
+            columns,
+            tile_names: HashMap::new(),
+        }
+    }
+
+    /// Generates a chunk with given x and y coordinates and no tiles
+    #[must_use]
+    pub fn empty(x: i32, y: i32) -> Self {
+        let mut chunk = Self::new(x, y);
+        chunk.layers = (0..CHUNK_DIMENSIONS.2).map(|z| Layer::new(z, None)).collect();
+        chunk
+    }
+
+    /// Gets the type of the tile at the given position, `None` if there is no tile or it is out of the chunk
+    #[must_use]
+    pub fn get_tile(&self, pos: &Position) -> Option<TileType> {
+        let index = Layer::tile_index((self.x, self.y), pos)?;
+        *self.layers.get(usize::try_from(pos.z).ok()?)?.tiles.get(index)?
+    }
+
+    /// Puts a tile of type `tile_type` at the given position and gives back the one that was there.
+    ///
+    /// Positions out of the chunk are ignored.
+    pub fn set_tile(&mut self, pos: &Position, tile_type: TileType) -> Option<TileType> {
+        self.tile_mut(pos)?.replace(tile_type)
+    }
+
+    /// Removes the tile at the given position and gives it back
+    pub fn remove_tile(&mut self, pos: &Position) -> Option<TileType> {
+        self.tile_mut(pos)?.take()
+    }
+
+    fn tile_mut(&mut self, pos: &Position) -> Option<&mut Option<TileType>> {
+        let index = Layer::tile_index((self.x, self.y), pos)?;
+        self.layers.get_mut(usize::try_from(pos.z).ok()?)?.tiles.get_mut(index)
+    }
+
+    /// Iterates over the tiles of the chunk, from the bottom layer to the top one
+    pub fn tiles(&self) -> impl Iterator<Item = TileData> + '_ {
+        let coords = (self.x, self.y);
+        self.layers.iter().flat_map(move |layer| {
+            layer.tiles.iter().enumerate().filter_map(move |(index, tile)| {
+                Some(TileData {
+                    pos: Layer::tile_position(coords, index, layer.z),
+                    tile_type: (*tile)?,
+                })
+            })
+        })
     }
 
-    /// Removes the given position from a chunk
-    pub fn remove_tile(&mut self, pos: Position) {
-        if let Some(layer) = self.layers.get_mut(pos.z as usize) {
-            if let Some(index) = layer.tiles.iter().position(|tile| tile.pos == pos) {
-                layer.tiles.remove(index);
+    /// Name of the tile raw used to spawn `tile`, the named tiles of [`Chunk::tile_names`] or else
+    /// the ones of the biome of its column
+    #[must_use]
+    pub fn tile_name(&self, tile: &TileData, raw_master: &RawMaster) -> String {
+        match self.tile_names.get(&tile.pos) {
+            Some(name) => name.clone(),
+            None => {
+                let column = self.column(&tile.pos).expect("expected the tile to be in its chunk");
+                raw_master.biome_tile_name(column, tile)
             }
         }
     }
 
-    // Finds the heighest tile in a given [`Position`] ignoring z
-    pub fn find_top_layer(&self, pos: &Position) -> i32 {
-        for z in (0..CHUNK_DIMENSIONS.2).rev() {
-            if let Some(layer) = self.layers.get(z as usize) {
-                if layer
-                    .tiles
-                    .iter()
-                    .any(|tile| tile.pos.x == pos.x && tile.pos.y == pos.y)
-                {
-                    return z;
-                }
+    /// Gets the terrain of the column of the given position
+    #[must_use]
+    pub fn column(&self, pos: &Position) -> Option<&Column> {
+        Layer::tile_index((self.x, self.y), pos).and_then(|index| self.columns.get(index))
+    }
+
+    /// Shapes the terrain of the chunk with the elevations and biomes of `map`, where `offset` are
+    /// the coordinates in `map` of the first column of the chunk, and carves the ground where the
+    /// `caves` noise, sampled at the tile positions, is over [`CAVES_THRESHOLD`]
+    ///
+    /// The surface turns into a ramp, or a stair in the rough biomes, where the next column towards
+    /// a side is one layer higher, the columns of the chunks around are read from the margin of
+    /// `map`. At the foot of the other higher columns it is a half or a quarter block, when the
+    /// terrain is that close to the next layer. The ocean columns are filled with water up to the
+    /// layer of the `map` sea level, and the surface of the river columns is flowing water.
+    pub fn shape_terrain(
+        &mut self,
+        map: &TerrainMap,
+        (offset_x, offset_y): (usize, usize),
+        caves: &impl NoiseFn<f64, 3>,
+    ) {
+        let (chunk_width, chunk_height, _) = CHUNK_DIMENSIONS;
+        let coords = (self.x, self.y);
+        // Coordinates in `map` of the column of `pos`, which can be in a chunk around this one. The
+        // rows of the layers go from the top to the bottom of the noise map
+        let map_coords = |pos: &Position| {
+            let chunk = pos.chunk();
+            let index = Layer::tile_index(chunk, pos)? as i32;
+            Some((
+                offset_x as i32 + (chunk.0 - coords.0) * chunk_width + index % chunk_width,
+                offset_y as i32 + (chunk.1 - coords.1) * chunk_height + chunk_height - 1 - index / chunk_width,
+            ))
+        };
+
+        let mut fractions = Vec::with_capacity(self.columns.len());
+        for (i, column) in self.columns.iter_mut().enumerate() {
+            let (x, y) = map_coords(&Layer::tile_position(coords, i, 0)).expect("expected the column in its chunk");
+            let elevation = map.elevation(x, y).unwrap_or_default();
+            *column = Column {
+                biome: map.biome(x as usize, y as usize),
+                elevation: elevation_layer(elevation),
+            };
+            fractions.push(elevation_fraction(elevation));
+        }
+        let surface: Vec<i32> = self.columns.iter().map(|column| column.elevation).collect();
+        // Surface of the column of `pos`, `None` out of `map`
+        let column_surface = |pos: &Position| {
+            let (x, y) = map_coords(pos)?;
+            map.elevation(x, y).map(elevation_layer)
+        };
+        let water_level = elevation_layer(map.sea_level);
+        let columns = &self.columns;
+        // The oceans are filled up to the sea level and the rivers flow over their bed
+        let water = |z: i32, i: usize| match columns[i].biome {
+            Biome::Ocean if (surface[i]..=water_level).contains(&z) => Some(if surface[i] == water_level {
+                TileType::ShallowWater
+            } else {
+                TileType::DeepWater
+            }),
+            Biome::River if z == surface[i] => Some(TileType::FlowingWater),
+            _ => None,
+        };
+
+        // The bottom layer is never carved so nothing falls out of the world
+        let is_carved = |pos: &Position, surface: i32| {
+            pos.z > 0
+                && pos.z <= surface
+                && caves.get([f64::from(pos.x), f64::from(pos.y), f64::from(pos.z)]) > CAVES_THRESHOLD
+        };
+        let carved: Vec<Vec<bool>> = self
+            .layers
+            .iter()
+            .map(|layer| {
+                (0..layer.tiles.len())
+                    .map(|i| is_carved(&Layer::tile_position(coords, i, layer.z), surface[i]))
+                    .collect()
+            })
+            .collect();
+        let solid = |z: i32, i: usize| z < surface[i] && !carved[z as usize][i];
+        // Side of the next column one layer higher, whose block and top are not carved
+        let climbs_to = |pos: Position| {
+            Orientation::ALL.into_iter().find(|orientation| {
+                let next = pos + orientation.step();
+                column_surface(&next) == Some(pos.z + 1)
+                    && !is_carved(&next, pos.z + 1)
+                    && !is_carved(&(next + Position::new(0, 0, 1)), pos.z + 1)
+            })
+        };
+        let surface_tile = |z: i32, i: usize| {
+            let pos = Layer::tile_position(coords, i, z);
+            if let Some(orientation) = climbs_to(pos) {
+                return if columns[i].biome.is_rough() {
+                    TileType::Stair(orientation)
+                } else {
+                    TileType::Ramp(orientation)
+                };
+            }
+            // Next to the block of a higher column
+            let higher = |orientation: Orientation| {
+                let next = pos + orientation.step();
+                column_surface(&next).is_some_and(|surface| surface > z && !is_carved(&next, surface))
+            };
+            match fractions[i] {
+                fraction if fraction >= 0.5 && Orientation::ALL.into_iter().any(higher) => TileType::HalfBlock,
+                fraction if fraction >= 0.25 && Orientation::ALL.into_iter().any(higher) => TileType::QuarterBlock,
+                _ => TileType::Floor,
+            }
+        };
+
+        for layer in &mut self.layers {
+            let z = layer.z;
+            for (i, tile) in layer.tiles.iter_mut().enumerate() {
+                *tile = if let Some(water) = water(z, i) {
+                    Some(water)
+                } else if solid(z, i) {
+                    Some(TileType::Block)
+                } else if z == surface[i] && (z == 0 || solid(z - 1, i) || !carved[z as usize][i]) {
+                    // Standing on a block, or the ground left over a cave
+                    Some(surface_tile(z, i))
+                } else if z < surface[i] && (z == 0 || solid(z - 1, i)) {
+                    // The floor of a cave
+                    Some(TileType::Floor)
+                } else {
+                    None
+                };
             }
         }
-        0
     }
+
+    /// Tells if there is a [`TileType::Block`] at the given position of the chunk
+    #[must_use]
+    pub fn is_block(&self, pos: &Position) -> bool {
+        self.get_tile(pos) == Some(TileType::Block)
+    }
+
+    // Finds the heighest tile in a given [`Position`] ignoring z
+    pub fn find_top_layer(&self, pos: &Position) -> i32 {
+        Layer::tile_index((self.x, self.y), pos)
+            .and_then(|index| self.layers.iter().rposition(|layer| layer.tiles[index].is_some()))
+            .map_or(0, |z| z as i32)
+    }
+}
+
+/// Layer of the terrain surface at the given `elevation`, from -1.0 to 1.0 like the planet ones
+fn elevation_layer(elevation: f64) -> i32 {
+    let chunk_layers = CHUNK_DIMENSIONS.2;
+    let elevation = (elevation * 0.5 + 0.5).clamp(0.0, 1.0);
+    (UNDERGROUND_LAYERS + (elevation * f64::from(chunk_layers - UNDERGROUND_LAYERS)) as i32).min(chunk_layers - 1)
+}
+
+/// How far the terrain surface at the given `elevation` is from its layer towards the next one, from
+/// 0.0 to 1.0
+fn elevation_fraction(elevation: f64) -> f64 {
+    let elevation = (elevation * 0.5 + 0.5).clamp(0.0, 1.0);
+    (elevation * f64::from(CHUNK_DIMENSIONS.2 - UNDERGROUND_LAYERS)).fract()
 }
 
 /// Takes a NoiseMap and map it to the chunks in the position marked by the bounds of the map
-pub fn split_map(map: &NoiseMap) -> HashMap<(i32, i32), Chunk> {
+pub fn split_map(map: &TerrainMap, caves: &impl NoiseFn<f64, 3>) -> HashMap<(i32, i32), Chunk> {
     let (map_width, map_height) = (map.size().0 as i32, map.size().1 as i32);
-    let (chunk_width, chunk_height, chunk_layers) = CHUNK_DIMENSIONS;
+    let (chunk_width, chunk_height, _) = CHUNK_DIMENSIONS;
 
     // Calculate the number of chunks needed based on the map dimensions
     let cols = (map_width / chunk_width) - 1;
@@ -65,42 +284,7 @@ pub fn split_map(map: &NoiseMap) -> HashMap<(i32, i32), Chunk> {
     let mut chunks = generate_mesh_of_chunks(cols, 0, rows, 0);
 
     for ((x, y), chunk) in chunks.iter_mut() {
-        let chunk_copy = chunk.clone();
-        for (j, tile) in chunk_copy.layers[0]
-            .tiles
-            .chunks_exact(chunk_width as usize)
-            .rev()
-            .flatten()
-            .enumerate()
-        {
-            let noise_x = x * chunk_width + (j as i32 % chunk_width);
-            let noise_y = y * chunk_height + (j as i32 / chunk_width);
-            if noise_x > map_width || noise_y > map_height || noise_x < 0 || noise_y < 0 {
-                break;
-            }
-
-            let z = ((map.get_value(noise_x as usize, noise_y as usize) * 0.5 + 0.5).clamp(0.0, 1.0)
-                * chunk_layers as f64) as i32;
-
-            // Find the tile at (tile.pos.x, tile.pos.y, z) and change it to Floor
-            if let Some(layer) = chunk.layers.get_mut(z as usize) {
-                if let Some(tile_at_z) = layer
-                    .tiles
-                    .iter_mut()
-                    .find(|t| t.pos.x == tile.pos.x && t.pos.y == tile.pos.y)
-                {
-                    tile_at_z.tile_type = TileType::Floor;
-                }
-            }
-
-            for layer in (z + 1)..chunk_layers {
-                chunk.remove_tile(Position {
-                    x: tile.pos.x,
-                    y: tile.pos.y,
-                    z: layer,
-                });
-            }
-        }
+        chunk.shape_terrain(map, ((x * chunk_width) as usize, (y * chunk_height) as usize), caves);
     }
 
     chunks
@@ -122,18 +306,241 @@ pub fn generate_mesh_of_chunks(max_x: i32, min_x: i32, max_y: i32, min_y: i32) -
 }
 /// Function to collect all tiles from chunks and sort them by z, then y, then x
 #[must_use]
-pub fn get_sorted_tiles(chunks: &HashMap<(i32, i32), Chunk>) -> Vec<TileData> {
-    let mut tiles: Vec<TileData> = chunks
-        .values()
-        .flat_map(|chunk| chunk.layers.iter().flat_map(|layer| layer.tiles.iter().cloned()))
-        .collect();
+pub fn get_sorted_tiles<'a>(chunks: impl IntoIterator<Item = &'a Chunk>) -> Vec<TileData> {
+    let mut tiles: Vec<TileData> = chunks.into_iter().flat_map(Chunk::tiles).collect();
 
     tiles.sort_by_key(|tile| (tile.pos.z, std::cmp::Reverse(tile.pos.y), std::cmp::Reverse(tile.pos.x)));
     tiles
 }
 
-// TODO create tests
+/// Collects the tiles of `chunk` that can be seen, sorted like [`get_sorted_tiles`].
+///
+/// A tile is hidden when there are blocks over it and on the two sides facing the camera, which
+/// depend on the `rotation` of the view, `is_block` tells if there is a block at the positions
+/// outside of the chunk. The tiles of the `view_level` the view is cut at show their top even
+/// under other blocks.
+#[must_use]
+pub fn get_visible_tiles(
+    chunk: &Chunk,
+    rotation: ViewRotation,
+    view_level: Option<i32>,
+    is_block: impl Fn(&Position) -> bool,
+) -> Vec<TileData> {
+    let covered = |pos: Position| {
+        if pos.chunk() == (chunk.x, chunk.y) {
+            chunk.is_block(&pos)
+        } else {
+            is_block(&pos)
+        }
+    };
+
+    let [left, right] = rotation.front_steps();
+    let mut tiles = get_sorted_tiles([chunk]);
+    tiles.retain(|tile| {
+        let pos = tile.pos;
+        view_level == Some(pos.z)
+            || !(covered(Position::new(pos.x, pos.y, pos.z + 1)) && covered(pos + left) && covered(pos + right))
+    });
+    tiles
+}
+
+/// Other chunks holding the tiles the border tiles of the chunk at `coords` are culled against, the
+/// ones in front of it seen from the side of `rotation`
+#[must_use]
+pub fn front_chunks(coords: (i32, i32), rotation: ViewRotation) -> HashSet<(i32, i32)> {
+    let steps = rotation.front_steps();
+    (0..(CHUNK_DIMENSIONS.0 * CHUNK_DIMENSIONS.1) as usize)
+        .map(|index| Layer::tile_position(coords, index, 0))
+        .flat_map(|pos| steps.map(|step| (pos + step).chunk()))
+        .filter(|front| *front != coords)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::WorldMap;
+
+    #[test]
+    fn chunks_generated_alone_match_the_split_map() {
+        let world_map = WorldMap {
+            size: (32, 32),
+            ..Default::default()
+        };
+        let chunks = split_map(&world_map.generate(), &world_map.caves());
+        for (&(x, y), chunk) in &chunks {
+            let mut alone = Chunk::new(x, y);
+            alone.shape_terrain(&world_map.generate_chunk((x, y)), (0, 0), &world_map.caves());
+            assert_eq!(get_sorted_tiles([&alone]), get_sorted_tiles([chunk]));
+            assert_eq!(alone.columns, chunk.columns);
+        }
+    }
+
+    #[test]
+    fn column_of_every_tile_is_found() {
+        let chunk = Chunk::new(-2, 3);
+        for i in 0..chunk.columns.len() {
+            let pos = Layer::tile_position((-2, 3), i, 0);
+            assert_eq!(Layer::tile_index((-2, 3), &pos), Some(i));
+            assert_eq!(pos.chunk(), (-2, 3));
+        }
+        assert_eq!(Layer::tile_index((-2, 3), &Layer::tile_position((-1, 3), 0, 0)), None);
+    }
+
+    #[test]
+    fn chunks_in_front_are_on_the_side_of_the_view() {
+        let front = front_chunks((-2, 3), ViewRotation::Deg0);
+        let back = front_chunks((-2, 3), ViewRotation::Deg180);
+        assert!(!front.is_empty() && !back.is_empty());
+        assert_ne!(front, back);
+        // Only the tiles next to the chunk are looked at
+        for coords in front.iter().chain(&back) {
+            assert!((coords.0 + 2).abs() <= 1 && (coords.1 - 3).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn top_of_every_column_is_ground_or_water() {
+        let world_map = WorldMap::default();
+        let mut chunk = Chunk::new(1, 2);
+        chunk.shape_terrain(&world_map.generate_chunk((1, 2)), (0, 0), &world_map.caves());
+        for i in 0..chunk.columns.len() {
+            let mut pos = Layer::tile_position((1, 2), i, 0);
+            pos.z = chunk.find_top_layer(&pos);
+            let top_tile = chunk.get_tile(&pos).expect("expected a tile on the top layer");
+            assert!(
+                top_tile.is_water()
+                    || matches!(
+                        top_tile,
+                        TileType::Floor
+                            | TileType::Ramp(_)
+                            | TileType::Stair(_)
+                            | TileType::HalfBlock
+                            | TileType::QuarterBlock
+                    )
+            );
+        }
+    }
+
+    #[test]
+    fn ramps_and_stairs_rise_to_a_column_one_layer_higher() {
+        let world_map = WorldMap::default();
+        let chunks = split_map(&world_map.generate(), &world_map.caves());
+        let mut orientations = HashSet::new();
+        let (mut stairs, mut across_chunks) = (0, 0);
+        for chunk in chunks.values() {
+            for tile in chunk.tiles() {
+                let Some(orientation) = tile.tile_type.climbs_to() else { continue };
+                orientations.insert(orientation);
+                stairs += usize::from(matches!(tile.tile_type, TileType::Stair(_)));
+                // The columns of the chunks around the map are only in the margin of its terrain
+                let next = tile.pos + orientation.step();
+                let Some(next_chunk) = chunks.get(&next.chunk()) else { continue };
+                across_chunks += usize::from(next.chunk() != (chunk.x, chunk.y));
+                let upper = next + Position::new(0, 0, 1);
+                assert_eq!(next_chunk.find_top_layer(&upper), upper.z);
+                assert!(next_chunk.is_block(&next));
+            }
+        }
+        assert_eq!(orientations, HashSet::from(Orientation::ALL));
+        assert!(stairs > 0 && across_chunks > 0);
+    }
+
+    #[test]
+    fn partial_blocks_lie_at_the_foot_of_higher_columns() {
+        let world_map = WorldMap::default();
+        let chunks = split_map(&world_map.generate(), &world_map.caves());
+        let is_block = |pos: &Position| chunks.get(&pos.chunk()).map(|chunk| chunk.is_block(pos));
+        let mut partial_blocks = 0;
+        for tile in chunks.values().flat_map(Chunk::tiles) {
+            if !matches!(tile.tile_type, TileType::HalfBlock | TileType::QuarterBlock) {
+                continue;
+            }
+            partial_blocks += 1;
+            let sides = Orientation::ALL.map(|orientation| is_block(&(tile.pos + orientation.step())));
+            // The higher column can be out of the map
+            assert!(sides.iter().any(|side| side.is_none_or(|block| block)));
+        }
+        assert!(partial_blocks > 0);
+    }
+
+    #[test]
+    fn oceans_are_filled_up_to_the_sea_level() {
+        let world_map = WorldMap::default();
+        let terrain_map = world_map.generate();
+        let water_level = elevation_layer(terrain_map.sea_level);
+        let chunks = split_map(&terrain_map, &world_map.caves());
+        let mut biomes = HashSet::new();
+        for chunk in chunks.values() {
+            for (i, column) in chunk.columns.iter().enumerate() {
+                biomes.insert(column.biome);
+                let mut pos = Layer::tile_position((chunk.x, chunk.y), i, 0);
+                pos.z = chunk.find_top_layer(&pos);
+                let top_tile = chunk.get_tile(&pos).expect("expected a tile on the top layer");
+                match column.biome {
+                    Biome::Ocean => {
+                        assert_eq!(pos.z, water_level);
+                        assert!(matches!(top_tile, TileType::ShallowWater | TileType::DeepWater));
+                    }
+                    Biome::River => assert_eq!(top_tile, TileType::FlowingWater),
+                    _ => assert!(!top_tile.is_water()),
+                }
+            }
+        }
+        assert!(biomes.contains(&Biome::Ocean) && biomes.contains(&Biome::River));
+    }
+
+    #[test]
+    fn tiles_are_set_and_removed_by_position() {
+        let mut chunk = Chunk::empty(-1, 2);
+        let pos = Layer::tile_position((-1, 2), 37, 5);
+        assert_eq!(chunk.get_tile(&pos), None);
+        assert_eq!(chunk.set_tile(&pos, TileType::Floor), None);
+        assert_eq!(chunk.set_tile(&pos, TileType::HalfBlock), Some(TileType::Floor));
+        assert_eq!(chunk.get_tile(&pos), Some(TileType::HalfBlock));
+        assert_eq!(chunk.find_top_layer(&pos), 5);
+        assert_eq!(
+            chunk.tiles().collect::<Vec<_>>(),
+            vec![TileData {
+                pos,
+                tile_type: TileType::HalfBlock
+            }]
+        );
+        assert_eq!(chunk.remove_tile(&pos), Some(TileType::HalfBlock));
+        assert_eq!(chunk.get_tile(&pos), None);
+
+        // Out of the chunk nothing is stored
+        let outside = Layer::tile_position((0, 2), 0, 5);
+        assert_eq!(chunk.set_tile(&outside, TileType::Floor), None);
+        assert_eq!(chunk.get_tile(&outside), None);
+        assert_eq!(chunk.tiles().count(), 0);
+    }
+
+    #[test]
+    fn only_the_outside_of_a_full_chunk_and_its_cut_level_are_visible() {
+        let chunk = Chunk::new(0, 0);
+        let top = CHUNK_DIMENSIONS.2 - 1;
+        // Around the chunk there is nothing, so its top and its sides facing the camera are seen
+        let visible = get_visible_tiles(&chunk, ViewRotation::Deg0, None, |_| false);
+        assert!(visible.iter().all(|tile| {
+            let pos = tile.pos;
+            pos.z == top
+                || !chunk.is_block(&(pos + Position::new(-1, 0, 0)))
+                || !chunk.is_block(&(pos + Position::new(0, -1, 0)))
+        }));
+
+        // Turned, other sides face the camera
+        let turned = get_visible_tiles(&chunk, ViewRotation::Deg180, None, |_| false);
+        assert_ne!(visible, turned);
+        assert_eq!(visible.len(), turned.len());
+
+        // Cutting the view shows the whole layer of its level
+        let cut = get_visible_tiles(&chunk, ViewRotation::Deg0, Some(10), |_| false);
+        let (width, height, _) = CHUNK_DIMENSIONS;
+        assert_eq!(
+            cut.iter().filter(|tile| tile.pos.z == 10).count(),
+            (width * height) as usize
+        );
+        assert_eq!(cut.len(), visible.len() + ((width - 1) * (height - 1)) as usize);
+    }
 }
@@ -0,0 +1,147 @@
+// This lint usually gives bad advice in the context of Bevy -- hiding complex queries behind
+// type aliases tends to obfuscate code while offering no improvement in code cleanliness.
+#![allow(clippy::type_complexity)]
+
+use crate::menus::{NORMAL_BUTTON, TEXT_COLOR};
+use crate::systems::fog_visibility_system;
+use crate::{CurrentMap, GameState, Position};
+use bevy::prelude::*;
+
+pub struct CutawayPlugin;
+
+impl Plugin for CutawayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), cutaway_setup)
+            .add_systems(
+                Update,
+                (
+                    change_view_level,
+                    (view_level_text_system, hide_sprites.after(fog_visibility_system)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(OnExit(GameState::InGame), clear_view_level);
+    }
+}
+
+// All actions that can be triggered from a button of the view level panel
+#[derive(Component, Debug, Clone, Copy)]
+pub(super) enum ViewLevelAction {
+    Up,
+    Down,
+    ShowAll,
+}
+
+/// Marker of the text showing the level the view is cut at
+#[derive(Component)]
+struct ViewLevelText;
+
+fn cutaway_setup(mut commands: Commands) {
+    let button_node = Node {
+        width: Val::Px(40.0),
+        height: Val::Px(30.0),
+        margin: UiRect::all(Val::Px(4.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let text_style = (
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+
+    commands
+        .spawn((
+            Name::new("ViewLevelPanel"),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            DespawnOnExit(GameState::InGame),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Level: all"), text_style.clone(), ViewLevelText));
+            for (action, text) in [
+                (ViewLevelAction::Up, "+"),
+                (ViewLevelAction::Down, "-"),
+                (ViewLevelAction::ShowAll, "All"),
+            ] {
+                parent
+                    .spawn((Button, button_node.clone(), BackgroundColor(NORMAL_BUTTON), action))
+                    .with_children(|parent| {
+                        parent.spawn((Text::new(text), text_style.clone()));
+                    });
+            }
+        });
+}
+
+/// Moves the level the view is cut at with PageUp and PageDown or the buttons of the panel, Home
+/// shows every layer again.
+///
+/// The first cut is made at the surface under the middle of the screen.
+pub(super) fn change_view_level(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    interaction_query: Query<(&Interaction, &ViewLevelAction), (Changed<Interaction>, With<Button>)>,
+    camera: Single<&Transform, With<Camera>>,
+    mut current_map: ResMut<CurrentMap>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .find_map(|(interaction, action)| (*interaction == Interaction::Pressed).then_some(*action));
+    let action = if keyboard_input.just_pressed(KeyCode::PageUp) {
+        ViewLevelAction::Up
+    } else if keyboard_input.just_pressed(KeyCode::PageDown) {
+        ViewLevelAction::Down
+    } else if keyboard_input.just_pressed(KeyCode::Home) {
+        ViewLevelAction::ShowAll
+    } else if let Some(action) = pressed {
+        action
+    } else {
+        return;
+    };
+
+    let layout = &current_map.layout;
+    let top = layout.top_layer - 1;
+    let view_level = match (action, layout.view_level) {
+        (ViewLevelAction::Up, Some(level)) if level + 1 < top => Some(level + 1),
+        (ViewLevelAction::Up | ViewLevelAction::ShowAll, _) => None,
+        (ViewLevelAction::Down, Some(level)) => Some((level - 1).max(0)),
+        (ViewLevelAction::Down, None) => {
+            let surface = layout.world_to_tile(camera.translation.truncate(), |pos| current_map.tiles.contains(pos));
+            Some(surface.map_or(top, |pos| pos.z))
+        }
+    };
+    current_map.layout.view_level = view_level;
+}
+
+// This system shows the level the view is cut at in the panel
+fn view_level_text_system(current_map: Res<CurrentMap>, mut text: Single<&mut Text, With<ViewLevelText>>) {
+    let shown = match current_map.layout.view_level {
+        Some(level) => format!("Level: {level}"),
+        None => "Level: all".to_string(),
+    };
+    if text.0 != shown {
+        text.0 = shown;
+    }
+}
+
+/// Hides the creatures, items and highlights above the level the view is cut at, the others keep
+/// the visibility the fog of war gives them
+fn hide_sprites(current_map: Res<CurrentMap>, mut sprites_query: Query<(&Position, &mut Visibility), With<Sprite>>) {
+    for (pos, mut visibility) in &mut sprites_query {
+        if current_map.layout.is_cut_away(pos.z) {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
+
+fn clear_view_level(mut current_map: ResMut<CurrentMap>) {
+    current_map.layout.view_level = None;
+}
@@ -0,0 +1,52 @@
+use super::save_tmx;
+use crate::{CurrentMap, GameState, Position, RawMaster, WorldChunks};
+use bevy::prelude::*;
+
+/// Where the map is exported, the sprites of the tilesets are relative to the assets folder
+pub(super) const EXPORTED_MAP_FILE: &str = "./assets/exported_map.tmx";
+
+pub struct MapExportPlugin;
+
+impl Plugin for MapExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, export_map_system.run_if(in_state(GameState::InGame)));
+    }
+}
+
+// This system writes the generated chunks to a TMX file when F2 is pressed
+fn export_map_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    raw_master: Res<RawMaster>,
+    current_map: Res<CurrentMap>,
+    world_chunks: Res<WorldChunks>,
+    names_query: Query<&Name>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F2) {
+        return;
+    }
+
+    // Every generated tile is exported, not only the ones spawned around the camera
+    let tiles: Vec<(String, Position)> = world_chunks
+        .chunks
+        .values()
+        .flat_map(|chunk| {
+            chunk
+                .tiles()
+                .map(|tile| (chunk.tile_name(&tile, &raw_master), tile.pos))
+        })
+        .collect();
+    // Decorations share the position of the tile under them, so they go with the objects
+    let decorations = world_chunks.decorations.values().flatten().cloned();
+    let objects: Vec<(String, Position)> = current_map
+        .entities
+        .iter()
+        .chain(current_map.items.iter())
+        .filter_map(|(pos, entity)| Some((names_query.get(entity).ok()?.to_string(), pos)))
+        .chain(decorations)
+        .collect();
+
+    match save_tmx(EXPORTED_MAP_FILE, &tiles, &objects, &raw_master) {
+        Ok(()) => info!("Map exported to {}", EXPORTED_MAP_FILE),
+        Err(err) => warn!("Unable to export the map to {}: {}", EXPORTED_MAP_FILE, err),
+    }
+}
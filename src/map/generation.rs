@@ -1,18 +1,12 @@
+use super::placement::place_scattered;
+use super::streaming::SURFACE_MIDDLE;
 use super::*;
 use crate::{
-    GameState, SpawnType, WorldCreationState, WorldMap,
-    map::chunks::{get_sorted_tiles, split_map},
+    CurrentMap, GameState, RawMaster, SpawnType, WorldChunks, WorldCreationState, WorldMap, map::chunks::split_map,
     spawner::SpawnEntity,
 };
 use bevy::prelude::*;
 
-// TODO convert this to resources in map creation
-#[allow(dead_code)]
-const CHUNK_SIZE: Vec2 = Vec2::new(
-    32.0 * CHUNK_DIMENSIONS.0 as f32,
-    32.0 * (CHUNK_DIMENSIONS.1 as f32 / 4.0),
-);
-
 pub struct MapGenerationPlugin;
 
 impl Plugin for MapGenerationPlugin {
@@ -32,105 +26,61 @@ fn map_generation_startup(
     mut map_creation_state: ResMut<NextState<WorldCreationState>>,
     mut app_state: ResMut<NextState<GameState>>,
     mut spawn_event: MessageWriter<SpawnEntity>,
-    //window: Query<&mut Window>,
     world_map: Res<WorldMap>,
+    raw_master: Res<RawMaster>,
+    mut world_chunks: ResMut<WorldChunks>,
+    current_map: Res<CurrentMap>,
+    mut camera: Single<&mut Transform, With<Camera>>,
 ) {
-    let noise_map = world_map.generate();
+    let terrain_map = world_map.generate();
 
-    // TODO to be used later in conjuction with noise map
-    //let window = window.single();
-    //let chunks_wide = (window.width() / CHUNK_SIZE.x).ceil();
-    //let chunks_height = (window.height() / CHUNK_SIZE.y).ceil();
-    //let cols = (chunks_wide / 2.0) as i32;
-    //let rows = (chunks_height / 2.0) as i32;
+    // The tiles are spawned by the chunk streaming around the camera
+    let chunks = split_map(&terrain_map, &world_map.caves());
+    world_chunks.decorations.clear();
+    world_chunks.bounded = false;
+    world_chunks.modified.clear();
+    world_chunks.scattered.clear();
 
-    let chunks = split_map(&noise_map);
-    get_sorted_tiles(&chunks).into_iter().for_each(|tile| {
-        // Checkboard pattern
-        //let (chunk_x, chunk_y) = tile.chunk();
-        //let name = if (chunk_x + chunk_y) % 2 == 0 { "GrassBlock".to_string() } else { "StoneBlock".to_string() };
+    // The colony starts at the embark chunk the player chose, from the same origin of its chunk
+    let mut embark = position(0, 0, 0);
+    let _ = embark.to_absolute(world_map.embark);
+    let top_position = |x: i32, y: i32| {
+        let pos = position(embark.x + x, embark.y + y, 0);
+        let chunk = chunks.get(&pos.chunk())?;
+        Some(position(pos.x, pos.y, chunk.find_top_layer(&pos)))
+    };
 
-        // z levels
-        let material = match tile.pos.z {
-            ..1 => "Stone".to_string(),
-            1..2 => "Sand".to_string(),
-            _ => "Grass".to_string(),
-        };
-        let style = match tile.tile_type {
-            TileType::Block => "Block".to_string(),
-            TileType::Floor => "Floor".to_string(),
-        };
-        let name = format!("{}{}", material, style).to_string();
+    match top_position(0, 0) {
+        Some(Position { x, y, z }) => {
+            spawn_event.write(SpawnEntity {
+                name: "Dummy".to_string(),
+                pos: SpawnType::AtPosition { x, y, z },
+            });
+        }
+        None => warn!("Dummy is out of the generated map"),
+    }
+    // A torch lights the embark through the first night
+    if let Some(Position { x, y, z }) = top_position(1, 1) {
         spawn_event.write(SpawnEntity {
-            name,
-            pos: SpawnType::AtPosition {
-                x: tile.pos.x,
-                y: tile.pos.y,
-                z: tile.pos.z,
-            },
+            name: "Torch".to_string(),
+            pos: SpawnType::AtPosition { x, y, z },
         });
-    });
-
-    let x = 0;
-    let y = 0;
-    let z = chunks
-        .get(&position(x, y, 0).chunk())
-        .expect("extected to find a chunk")
-        .find_top_layer(&position(x, y, 0));
-    spawn_event.write(SpawnEntity {
-        name: "Dummy".to_string(),
-        pos: SpawnType::AtPosition { x, y, z },
-    });
-    //let x = 20;
-    //let y = 20;
-    //let z = chunks
-    //    .get(&position(x, y, 0).chunk())
-    //    .expect("extected to find a chunk")
-    //    .find_top_layer(&position(x, y, 0));
+    }
     //spawn_event.write(SpawnEntity {
     //    name: "BadDummy".to_string(),
     //    pos: SpawnType::AtPosition { x, y, z },
     //});
-    let x = 10;
-    let y = 10;
-    let z = chunks
-        .get(&position(x, y, 0).chunk())
-        .expect("extected to find a chunk")
-        .find_top_layer(&position(x, y, 0));
-    spawn_event.write(SpawnEntity {
-        name: "Heart".to_string(),
-        pos: SpawnType::AtPosition { x, y, z },
-    });
-    let x = 15;
-    let y = 5;
-    let z = chunks
-        .get(&position(x, y, 0).chunk())
-        .expect("extected to find a chunk")
-        .find_top_layer(&position(x, y, 0));
-    spawn_event.write(SpawnEntity {
-        name: "RustySword".to_string(),
-        pos: SpawnType::AtPosition { x, y, z },
-    });
-    let x = 5;
-    let y = 5;
-    let z = chunks
-        .get(&position(x, y, 0).chunk())
-        .expect("extected to find a chunk")
-        .find_top_layer(&position(x, y, 0));
-    spawn_event.write(SpawnEntity {
-        name: "TreeWithFruit".to_string(),
-        pos: SpawnType::AtPosition { x, y, z },
-    });
-    let x = 5;
-    let y = 15;
-    let z = chunks
-        .get(&position(x, y, 0).chunk())
-        .expect("extected to find a chunk")
-        .find_top_layer(&position(x, y, 0));
-    spawn_event.write(SpawnEntity {
-        name: "Tree".to_string(),
-        pos: SpawnType::AtPosition { x, y, z },
-    });
+    // The vegetation and items of the chunks streamed later are scattered when they are generated
+    for chunk in chunks.values() {
+        place_scattered(chunk, world_map.seed, &raw_master, &mut world_chunks, &mut spawn_event);
+    }
+
+    // The chunk streaming follows the camera, so it starts over the embark
+    camera.translation = current_map
+        .layout
+        .tile_to_world_pos(position(embark.x, embark.y, SURFACE_MIDDLE))
+        .with_z(camera.translation.z);
+    world_chunks.chunks = chunks;
 
     // TODO this should be moved out
     map_creation_state.set(WorldCreationState::Disabled);
@@ -0,0 +1,88 @@
+use super::{TmxMap, load_tmx};
+use crate::{
+    CurrentMap, GameState, Position, RawMaster, SpawnType, WorldChunks, WorldCreationState, spawner::SpawnEntity,
+};
+use bevy::prelude::*;
+
+/// Hand-authored map played instead of a generated one
+pub(super) const TMX_MAP_FILE: &str = "./assets/iso_map.tmx";
+
+/// TMX file loaded on entering [`WorldCreationState::MapImport`], chosen on the map settings screen
+#[derive(Resource, Debug)]
+pub(super) struct MapImportFile(pub &'static str);
+
+impl Default for MapImportFile {
+    fn default() -> Self {
+        Self(TMX_MAP_FILE)
+    }
+}
+
+pub struct MapImportPlugin;
+
+impl Plugin for MapImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapImportFile>()
+            .add_systems(OnEnter(WorldCreationState::MapImport), map_import_startup);
+    }
+}
+
+fn map_import_startup(
+    mut map_creation_state: ResMut<NextState<WorldCreationState>>,
+    mut app_state: ResMut<NextState<GameState>>,
+    map_file: Res<MapImportFile>,
+    mut spawn_event: MessageWriter<SpawnEntity>,
+    raw_master: Res<RawMaster>,
+    mut world_chunks: ResMut<WorldChunks>,
+    current_map: Res<CurrentMap>,
+    mut camera: Single<&mut Transform, With<Camera>>,
+) {
+    let file = map_file.0;
+    let TmxMap { chunks, objects } = match load_tmx(file, &raw_master) {
+        Ok(map) => map,
+        Err(err) => {
+            warn!("Unable to load the map {}: {}", file, err);
+            map_creation_state.set(WorldCreationState::MapSettings);
+            return;
+        }
+    };
+
+    // The tiles are spawned by the chunk streaming around the camera, that starts over the middle of the map
+    let positions = chunks.values().flat_map(|chunk| chunk.tiles().map(|tile| tile.pos));
+    let (count, sum) = positions.fold((0, IVec2::ZERO), |(count, sum), pos| {
+        (count + 1, sum + IVec2::new(pos.x, pos.y))
+    });
+    let middle = sum / count.max(1);
+    camera.translation = current_map
+        .layout
+        .tile_to_world_pos(Position::new(middle.x, middle.y, 0))
+        .with_z(camera.translation.z);
+
+    world_chunks.chunks = chunks;
+    world_chunks.decorations.clear();
+    world_chunks.bounded = true;
+    world_chunks.modified.clear();
+    world_chunks.scattered.clear();
+
+    // Tiles like trees are spawned with their chunk, creatures and items right away
+    for (name, pos) in objects {
+        if raw_master.tile_index.contains_key(&name) {
+            world_chunks
+                .decorations
+                .entry(pos.chunk())
+                .or_default()
+                .push((name, pos));
+        } else {
+            spawn_event.write(SpawnEntity {
+                name,
+                pos: SpawnType::AtPosition {
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                },
+            });
+        }
+    }
+
+    map_creation_state.set(WorldCreationState::Disabled);
+    app_state.set(GameState::InGame);
+}
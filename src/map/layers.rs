@@ -1,68 +1,87 @@
 use super::CHUNK_DIMENSIONS;
 use crate::{Position, TileType};
 use bevy::prelude::Component;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct TileData {
     pub pos: Position,
     pub tile_type: TileType,
 }
 
-/// A layer of a chunk, containing tiles and its z-coordinate.
-#[derive(Component, Debug, Clone)]
+/// A layer of a chunk, a dense grid of the tiles of its columns at its z-coordinate.
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Layer {
     /// The z-coordinate of this layer.
     pub z: i32,
-    /// The tiles (positions) contained in this layer.
-    pub tiles: Vec<TileData>,
+    /// The tile of each column, indexed like [`Layer::tile_index`], `None` where there is no tile.
+    pub tiles: Vec<Option<TileType>>,
 }
 
 impl Layer {
-    /// Creates a new `Layer` for the given chunk coordinates and `z` level.
+    /// Creates a new `Layer` at the `z` level with every column holding `tile`.
     ///
     /// # Parameters
-    /// - `chunk_x`: The x-coordinate of the chunk.
-    /// - `chunk_y`: The y-coordinate of the chunk.
     /// - `z`: The z-coordinate of the layer.
+    /// - `tile`: The tile of every column, `None` for an empty layer.
     ///
     /// # Returns
-    /// A new `Layer` instance with tiles adjusted for the chunk position.
+    /// A new `Layer` instance with one tile for each column of a chunk.
     #[must_use]
-    pub fn new(chunk_x: i32, chunk_y: i32, z: i32) -> Self {
-        let mut layer = Self::default();
-        layer.z = z;
-        layer.tiles.iter_mut().for_each(|tile| {
-            let _ = tile.pos.to_absolute((chunk_x, chunk_y));
-            tile.pos.z = z
-        });
-        layer
+    pub fn new(z: i32, tile: Option<TileType>) -> Self {
+        Self {
+            z,
+            tiles: vec![tile; (CHUNK_DIMENSIONS.0 * CHUNK_DIMENSIONS.1) as usize],
+        }
     }
-}
 
-impl Default for Layer {
-    fn default() -> Self {
-        let z = 0;
+    /// Computes the index in the `tiles` of a layer of the chunk at `chunk` of the column of `pos`
+    ///
+    /// # Returns
+    /// `None` if the position is not in the chunk.
+    #[must_use]
+    pub fn tile_index(chunk: (i32, i32), pos: &Position) -> Option<usize> {
         let chunk_side = CHUNK_DIMENSIONS.0;
-        // Base offsets for rows and columns
-        let base_x_offset = -(chunk_side / 4);
-        let base_y_offset = chunk_side - chunk_side / 3;
+        let (base_x_offset, base_y_offset) = base_offsets();
+        let origin = chunk_origin(chunk);
+
+        // Each row moves one step back in the sum of the coordinates
+        let (x, y) = (pos.x - origin.x, pos.y - origin.y);
+        let row = base_x_offset + base_y_offset - (x + y);
+        let col = x - base_x_offset + row / 2;
+        ((0..chunk_side).contains(&row) && (0..chunk_side).contains(&col)).then(|| (row * chunk_side + col) as usize)
+    }
 
-        let tiles = (0..chunk_side)
-            .flat_map(|row| {
-                // Precompute row-based offsets
-                let row_x_offset = base_x_offset - (row / 2);
-                let row_y_offset = base_y_offset - ((1 + row) / 2);
+    /// Computes the position at the layer `z` of the column at `index` in the `tiles` of a layer of
+    /// the chunk at `chunk`, the inverse of [`Layer::tile_index`]
+    #[must_use]
+    pub fn tile_position(chunk: (i32, i32), index: usize, z: i32) -> Position {
+        let chunk_side = CHUNK_DIMENSIONS.0;
+        let (base_x_offset, base_y_offset) = base_offsets();
+        let origin = chunk_origin(chunk);
 
-                (0..chunk_side).map(move |col| TileData {
-                    pos: Position::new(row_x_offset + col, row_y_offset - col, z),
-                    tile_type: TileType::Block,
-                })
-            })
-            .collect();
-        Self { z, tiles }
+        let (row, col) = (index as i32 / chunk_side, index as i32 % chunk_side);
+        Position::new(
+            origin.x + base_x_offset - (row / 2) + col,
+            origin.y + base_y_offset - ((1 + row) / 2) - col,
+            z,
+        )
     }
 }
 
+/// Base offsets for rows and columns
+fn base_offsets() -> (i32, i32) {
+    let chunk_side = CHUNK_DIMENSIONS.0;
+    (-(chunk_side / 4), chunk_side - chunk_side / 3)
+}
+
+/// Position the local coordinates of the chunk at `chunk` are relative to
+fn chunk_origin(chunk: (i32, i32)) -> Position {
+    let mut origin = Position::new(0, 0, 0);
+    let _ = origin.to_absolute(chunk);
+    origin
+}
+
 //#[cfg(test)]
 //mod tests {
 //    use super::*;
@@ -1,12 +1,76 @@
 use super::{matrix::ProjectionMatrix, CHUNK_DIMENSIONS};
 use crate::Position;
 use bevy::prelude::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
 
 pub const TILE_SIZE: Vec2 = Vec2::splat(32.0);
 
+/// Side the world is seen from, the view turns in quarters of a turn
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ViewRotation {
+    /// +x goes to the top right of the screen and +y to the top left
+    #[default]
+    Deg0,
+    /// +x goes to the bottom right of the screen and +y to the top right
+    Deg90,
+    /// +x goes to the bottom left of the screen and +y to the bottom right
+    Deg180,
+    /// +x goes to the top left of the screen and +y to the bottom left
+    Deg270,
+}
+
+impl ViewRotation {
+    pub const ALL: [Self; 4] = [Self::Deg0, Self::Deg90, Self::Deg180, Self::Deg270];
+
+    #[must_use]
+    pub fn quarter_turns(self) -> usize {
+        self as usize
+    }
+
+    /// Rotation with the world turned a quarter of a turn clockwise on the screen
+    #[must_use]
+    pub fn clockwise(self) -> Self {
+        Self::ALL[(self.quarter_turns() + 1) % 4]
+    }
+
+    /// Rotation with the world turned a quarter of a turn counter-clockwise on the screen
+    #[must_use]
+    pub fn counter_clockwise(self) -> Self {
+        Self::ALL[(self.quarter_turns() + 3) % 4]
+    }
+
+    /// Tells if the x and y axes of the world go along the other diagonal of the screen
+    #[must_use]
+    pub fn is_sideways(self) -> bool {
+        self.quarter_turns() % 2 == 1
+    }
+
+    /// Position in the unturned view of the world position `pos`
+    #[must_use]
+    pub fn turn(self, pos: Position) -> Position {
+        (0..self.quarter_turns()).fold(pos, |pos, _| Position::new(pos.y, -pos.x, pos.z))
+    }
+
+    /// World position of the position `pos` in the unturned view
+    #[must_use]
+    pub fn turn_back(self, pos: Position) -> Position {
+        (0..self.quarter_turns()).fold(pos, |pos, _| Position::new(-pos.y, pos.x, pos.z))
+    }
+
+    /// Steps on the same layer towards the two sides of the tiles facing the camera
+    #[must_use]
+    pub fn front_steps(self) -> [Position; 2] {
+        [Position::new(-1, 0, 0), Position::new(0, -1, 0)].map(|step| self.turn_back(step))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Layout {
     pub projection: ProjectionMatrix,
+    /// Side the world is seen from, it is turned by `projection`
+    pub rotation: ViewRotation,
+    /// Layer the view is cut at, the ones above it are not drawn, every layer is drawn without it
+    pub view_level: Option<i32>,
     pub origin: Vec3,
     pub tile_size: Vec2,
     pub top_layer: i32,
@@ -20,6 +84,50 @@ impl Layout {
         let [x, y, z] = self.projection.forward(pos.to_array_f32());
         Vec3::new(x * self.tile_size.x, y * self.tile_size.y, z) + self.origin
     }
+
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    /// Computes the [`Position`] in the layer `z` that is drawn under the world/pixel coordinates `world_pos`
+    pub fn world_pos_to_tile(&self, world_pos: Vec2, z: i32) -> Position {
+        let point = (world_pos - self.origin.truncate()) / self.tile_size;
+        let [x, y, _] = self.projection.inverse([point.x, point.y, z as f32]);
+        Position::new(x.round() as i32, y.round() as i32, z)
+    }
+
+    #[must_use]
+    /// Finds the tile drawn on top at the world/pixel coordinates `world_pos`, from the upper layer
+    /// drawn down, among the ones `is_visible` tells are drawn.
+    ///
+    /// Tiles are taken as blocks, covering the hexagon of their top and their two front sides, and
+    /// the one lower in the screen is drawn over the others of its layer.
+    pub fn world_to_tile(&self, world_pos: Vec2, is_visible: impl Fn(&Position) -> bool) -> Option<Position> {
+        let half = self.tile_size / 2.0;
+        let highest = self.view_level.unwrap_or(self.top_layer - 1);
+        (0..=highest).rev().find_map(|z| {
+            let center = self.world_pos_to_tile(world_pos, z);
+            (-1..=1)
+                .flat_map(|x| (-1..=1).map(move |y| center + Position::new(x, y, 0)))
+                .filter(|pos| is_visible(pos))
+                .map(|pos| (pos, world_pos - self.tile_to_world_pos(pos).truncate()))
+                .filter(|(_, offset)| {
+                    offset.x.abs() <= half.x && offset.y.abs() <= half.y * (1.0 - offset.x.abs() / self.tile_size.x)
+                })
+                .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).reverse())
+                .map(|(pos, _)| pos)
+        })
+    }
+
+    /// Tells if the layer `z` is above the level the view is cut at
+    #[must_use]
+    pub fn is_cut_away(&self, z: i32) -> bool {
+        self.view_level.is_some_and(|level| z > level)
+    }
+
+    /// Turns the view to `rotation`
+    pub fn rotate(&mut self, rotation: ViewRotation) {
+        self.rotation = rotation;
+        self.projection = ProjectionMatrix::rotated(rotation.quarter_turns());
+    }
 }
 
 impl Default for Layout {
@@ -28,9 +136,81 @@ impl Default for Layout {
         let offset_center_tile = TILE_SIZE.y / 4.0;
         Self {
             projection: ProjectionMatrix::default(),
+            rotation: ViewRotation::default(),
+            view_level: None,
             origin: Vec3::new(0., -(offset_layers + offset_center_tile), 0.),
             tile_size: TILE_SIZE,
             top_layer: CHUNK_DIMENSIONS.2,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_are_found_under_their_drawing_in_every_rotation() {
+        let mut layout = Layout::default();
+        for rotation in ViewRotation::ALL {
+            layout.rotate(rotation);
+            for pos in [
+                Position::new(0, 0, 0),
+                Position::new(5, -3, 2),
+                Position::new(-7, 12, 20),
+            ] {
+                let world_pos = layout.tile_to_world_pos(pos);
+                assert_eq!(layout.world_pos_to_tile(world_pos.truncate(), pos.z), pos, "{rotation:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_tile_drawn_on_top_is_picked() {
+        let layout = Layout::default();
+        let (back, front, over) = (Position::new(1, 1, 5), Position::new(0, 0, 5), Position::new(1, 1, 6));
+        let front_center = layout.tile_to_world_pos(front).truncate();
+
+        // The front side of `back` is hidden by the top of `front`
+        let point = front_center + Vec2::new(0.0, 10.0);
+        assert_eq!(layout.world_to_tile(point, |pos| [back, front].contains(pos)), Some(front));
+        // Over its top, above `front`, only `back` is drawn
+        let point = front_center + Vec2::new(0.0, 20.0);
+        assert_eq!(layout.world_to_tile(point, |pos| [back, front].contains(pos)), Some(back));
+        // A tile of an upper layer is drawn over both
+        assert_eq!(layout.world_to_tile(point, |pos| [back, front, over].contains(pos)), Some(over));
+        assert_eq!(layout.world_to_tile(point, |_| false), None);
+    }
+
+    #[test]
+    fn layers_above_the_view_level_are_not_picked() {
+        let mut layout = Layout::default();
+        let (under, over) = (Position::new(0, 0, 5), Position::new(0, 0, 6));
+        let point = layout.tile_to_world_pos(over).truncate();
+        assert_eq!(layout.world_to_tile(point, |pos| [under, over].contains(pos)), Some(over));
+
+        layout.view_level = Some(5);
+        assert!(layout.is_cut_away(6) && !layout.is_cut_away(5));
+        // The top of `under` is drawn half a tile lower than the one of `over`
+        let point = point - Vec2::new(0.0, 8.0);
+        assert_eq!(layout.world_to_tile(point, |pos| [under, over].contains(pos)), Some(under));
+    }
+
+    #[test]
+    fn a_quarter_turn_moves_the_front_sides() {
+        assert_eq!(
+            ViewRotation::Deg0.front_steps(),
+            [Position::new(-1, 0, 0), Position::new(0, -1, 0)]
+        );
+        // +x goes to the bottom right, so the +x side faces the camera
+        assert_eq!(
+            ViewRotation::Deg90.front_steps(),
+            [Position::new(0, -1, 0), Position::new(1, 0, 0)]
+        );
+        for rotation in ViewRotation::ALL {
+            assert_eq!(rotation.clockwise().counter_clockwise(), rotation);
+            let pos = Position::new(3, -8, 1);
+            assert_eq!(rotation.turn_back(rotation.turn(pos)), pos);
+        }
+    }
+}
@@ -1,7 +1,7 @@
-// TODO this might be useful in the future if I want different projections otherwise simply replace the call in layout with the formula
 /// Isometric matrices and offset
 const ISO_TILE_POSITION_MATRIX: ProjectionMatrix = ProjectionMatrix {
     forward_matrix: [0.5, 0.25, -0.5, 0.25, 0.0, 0.5],
+    inverse_matrix: [1.0, -1.0, 2.0, 2.0],
 };
 
 /// Matrix for isometric projection
@@ -9,17 +9,41 @@ const ISO_TILE_POSITION_MATRIX: ProjectionMatrix = ProjectionMatrix {
 pub struct ProjectionMatrix {
     /// Matrix used to compute isometric coordinates to world/pixel coordinates
     pub(crate) forward_matrix: [f32; 6],
+    /// Matrix used to compute world/pixel coordinates back to isometric coordinates on a layer, it
+    /// inverts the x and y columns of `forward_matrix`
+    pub(crate) inverse_matrix: [f32; 4],
 }
 
 impl Default for ProjectionMatrix {
     fn default() -> Self {
-        Self {
-            forward_matrix: ISO_TILE_POSITION_MATRIX.forward_matrix,
-        }
+        ISO_TILE_POSITION_MATRIX
     }
 }
 
 impl ProjectionMatrix {
+    /// Isometric projection of the positions turned `quarter_turns` times, each turn takes +x to -y
+    /// and +y to +x
+    #[must_use]
+    pub fn rotated(quarter_turns: usize) -> Self {
+        let mut matrix = ISO_TILE_POSITION_MATRIX.forward_matrix;
+        for _ in 0..quarter_turns % 4 {
+            // The new x column is the old -y one and the new y column the old x one
+            matrix = [-matrix[2], -matrix[3], matrix[0], matrix[1], matrix[4], matrix[5]];
+        }
+        Self::from_forward(matrix)
+    }
+
+    /// Projection applying `forward_matrix`, its x and y columns must not be parallel
+    #[must_use]
+    fn from_forward(forward_matrix: [f32; 6]) -> Self {
+        let [a, c, b, d, _, _] = forward_matrix;
+        let determinant = a.mul_add(d, -(b * c));
+        Self {
+            forward_matrix,
+            inverse_matrix: [d / determinant, -c / determinant, -b / determinant, a / determinant],
+        }
+    }
+
     #[must_use]
     #[inline]
     /// Applies `matrix` to a point defined by `x`, `y` and `z`
@@ -37,10 +61,41 @@ impl ProjectionMatrix {
     pub fn forward(&self, point: [f32; 3]) -> [f32; 3] {
         Self::matrix_op(self.forward_matrix, point)
     }
+
+    #[must_use]
+    #[inline]
+    /// Finds the isometric coordinates that [`Self::forward`] takes to the world/pixel coordinates of
+    /// `point`, on the layer given by its z
+    pub fn inverse(&self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        // The layer moves the point the same whatever its isometric x and y
+        let x = self.forward_matrix[4].mul_add(-z, x);
+        let y = self.forward_matrix[5].mul_add(-z, y);
+        let matrix = self.inverse_matrix;
+        [
+            x.mul_add(matrix[0], y * matrix[2]),
+            x.mul_add(matrix[1], y * matrix[3]),
+            z,
+        ]
+    }
 }
 
-// TODO create tests
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn inverse_undoes_forward_in_every_rotation() {
+        for quarter_turns in 0..4 {
+            let matrix = ProjectionMatrix::rotated(quarter_turns);
+            for point in [[0.0, 0.0, 0.0], [3.0, -2.0, 5.0], [-7.5, 12.25, 31.0]] {
+                let [x, y, z] = matrix.inverse(matrix.forward(point));
+                assert!(
+                    (x - point[0]).abs() < 1e-4 && (y - point[1]).abs() < 1e-4,
+                    "{quarter_turns}"
+                );
+                assert_eq!(z, point[2]);
+            }
+        }
+        assert_eq!(ProjectionMatrix::rotated(0), ProjectionMatrix::default());
+    }
 }
@@ -0,0 +1,117 @@
+use super::{Chunk, Layer};
+use crate::{Position, RawMaster, SpawnType, TileType, WorldChunks, spawner::SpawnEntity};
+use bevy::prelude::MessageWriter;
+
+/// Scatters the placements of the biomes over the floor at the top of the columns of `chunk`.
+///
+/// Each column rolls the placements of its biome in order and keeps the first one that is not
+/// closer than its spacing to anything already placed in the chunk. The same `seed` always gives
+/// the same names and positions.
+#[must_use]
+pub fn scatter(chunk: &Chunk, seed: u32, raw_master: &RawMaster) -> Vec<(String, Position)> {
+    let mut placed: Vec<(String, Position)> = Vec::new();
+    for (i, column) in chunk.columns.iter().enumerate() {
+        let mut pos = Layer::tile_position((chunk.x, chunk.y), i, 0);
+        pos.z = chunk.find_top_layer(&pos);
+        if chunk.get_tile(&pos) != Some(TileType::Floor) {
+            continue;
+        }
+        let placement = raw_master
+            .biome_placements(column.biome)
+            .iter()
+            .enumerate()
+            .find(|(salt, placement)| {
+                random(seed, &pos, *salt) < placement.density
+                    && placed
+                        .iter()
+                        .all(|(_, other)| (other.x - pos.x).abs().max((other.y - pos.y).abs()) > placement.spacing)
+            });
+        if let Some((_, placement)) = placement {
+            placed.push((placement.name.clone(), pos));
+        }
+    }
+    placed
+}
+
+/// Scatters the placements over a newly generated `chunk`, the tiles are kept with the
+/// decorations of the chunk and the items are spawned right away. A chunk is only scattered once,
+/// the items placed before are already in the world
+pub(super) fn place_scattered(
+    chunk: &Chunk,
+    seed: u32,
+    raw_master: &RawMaster,
+    world_chunks: &mut WorldChunks,
+    spawn_event: &mut MessageWriter<SpawnEntity>,
+) {
+    if !world_chunks.scattered.insert((chunk.x, chunk.y)) {
+        return;
+    }
+    for (name, pos) in scatter(chunk, seed, raw_master) {
+        if raw_master.tile_index.contains_key(&name) {
+            world_chunks
+                .decorations
+                .entry(pos.chunk())
+                .or_default()
+                .push((name, pos));
+        } else {
+            spawn_event.write(SpawnEntity {
+                name,
+                pos: SpawnType::AtPosition {
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                },
+            });
+        }
+    }
+}
+
+/// Value from 0.0 to 1.0 that only depends on the `seed`, the column of `pos` and the `salt`
+fn random(seed: u32, pos: &Position, salt: usize) -> f64 {
+    // SplitMix64 finalizer over the packed inputs
+    let mut hash = (u64::from(seed) << 32)
+        ^ u64::from(pos.x as u32).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ u64::from(pos.y as u32).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ (salt as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^= hash >> 31;
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Biome, WorldMap, read_raws, split_map};
+    use bevy::prelude::default;
+
+    #[test]
+    fn placements_are_reproducible_and_only_on_floor() {
+        let mut raw_master = RawMaster {
+            raws: read_raws(),
+            ..default()
+        };
+        raw_master.load();
+        let world_map = WorldMap::default();
+        let chunks = split_map(&world_map.generate(), &world_map.caves());
+        let mut placed = 0;
+        for chunk in chunks.values() {
+            let scattered = scatter(chunk, world_map.seed, &raw_master);
+            assert_eq!(scattered, scatter(chunk, world_map.seed, &raw_master));
+            for (name, pos) in &scattered {
+                let column = chunk.column(pos).expect("expected the placement to be in its chunk");
+                assert!(
+                    raw_master
+                        .biome_placements(column.biome)
+                        .iter()
+                        .any(|p| p.name == *name)
+                );
+                assert_ne!(column.biome, Biome::Ocean);
+                assert_eq!(chunk.find_top_layer(pos), pos.z);
+                assert_eq!(chunk.get_tile(pos), Some(TileType::Floor));
+            }
+            placed += scattered.len();
+        }
+        assert!(placed > 0);
+    }
+}
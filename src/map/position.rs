@@ -1,6 +1,7 @@
 #![allow(clippy::inline_always)]
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
 /// Collection of algorithms
@@ -23,7 +24,7 @@ pub(crate) use direction::*;
 pub(crate) use iter::ExactSizePositionIterator;
 
 /// Position Coordinates
-#[derive(Component, Reflect, Debug, Copy, Clone, Eq, Default, PartialEq, Hash)]
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Copy, Clone, Eq, Default, PartialEq, Hash)]
 pub struct Position {
     /// Position in the x coordinate (bottom-left to top-right)
     pub x: i32,
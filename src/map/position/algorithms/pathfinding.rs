@@ -1,11 +1,12 @@
-use crate::{position, CurrentMap, Position};
+use crate::{position, tile_walk_cost, tile_walkable, CurrentMap, Position};
 use bevy::prelude::{warn, Component, Reflect};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 mod a_star;
 use a_star::a_star;
 
-#[derive(Component, Reflect, Debug, Clone, Eq, PartialEq, Default)]
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
 pub struct PathfindingSteps(VecDeque<Position>);
 
 impl PathfindingSteps {
@@ -33,6 +34,14 @@ impl PathfindingSteps {
         self.0.back()
     }
 
+    pub fn contains(&self, pos: &Position) -> bool {
+        self.0.contains(pos)
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
     pub fn create_path(&mut self, o_pos: &Position, d_pos: &Position, grid: &CurrentMap) {
         if let Some(path) = find_path(o_pos, d_pos, grid) {
             self.0 = VecDeque::from(path);
@@ -44,19 +53,25 @@ impl PathfindingSteps {
 
 pub fn find_path(o_pos: &Position, d_pos: &Position, grid: &CurrentMap) -> Option<Vec<Position>> {
     a_star(*o_pos, *d_pos, |o, h| {
+        // Creatures walk over the tiles and only change of layer through ramps and stairs
+        let tile_type = *grid.tile_types.get(&h)?;
+        if !tile_walkable(tile_type) || (h.z != o.z && !grid.climbs(o, h)) {
+            return None;
+        }
+        // Wading through water is slower
+        let cost = tile_walk_cost(tile_type);
         // Implementation of blocked_coords
         if h.x == 0 || h.y == 0 {
             // Neighbor
-            (grid.tiles.contains_key(&h) && !grid.blocked_coords.contains(&h)).then_some(100)
+            (!grid.blocked_coords.contains(&h)).then_some(100 * cost)
         } else {
             // Diagonal
-            (grid.tiles.contains_key(&h)
-                && !grid.blocked_coords.contains(&h)
+            (!grid.blocked_coords.contains(&h)
                 && !grid.blocked_coords.contains(&position(h.x, o.y, o.z))
                 && !grid.blocked_coords.contains(&position(o.x, h.y, o.z)))
             // The diagonal move is 1.41 times the move distance to a neighbor
             // We use 100 times bigger to use u32 instead of float
-            .then_some(141)
+            .then_some(141 * cost)
         }
     })
 }
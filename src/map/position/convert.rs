@@ -1,5 +1,4 @@
 use crate::Position;
-use bevy::prelude::{Vec2, Vec3};
 
 impl From<(i32, i32, i32)> for Position {
     #[inline]
@@ -14,18 +13,3 @@ impl From<[f32; 3]> for Position {
         Self::round(v)
     }
 }
-
-impl From<Vec3> for Position {
-    #[inline]
-    fn from(value: Vec3) -> Self {
-        Self::from(value.to_array())
-    }
-}
-
-// TODO delete this and fix its dependencies
-impl From<Vec2> for Position {
-    #[inline]
-    fn from(value: Vec2) -> Self {
-        Self::from(Vec3::new(value.x, value.y, 0.0))
-    }
-}
@@ -1,7 +1,8 @@
 use bevy::prelude::{Component, Reflect};
+use serde::{Deserialize, Serialize};
 
 /// Default is 0 that correspond to the +X axis East and where Gandalf shall come
-#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
 pub struct Direction(pub(crate) u8);
 
 #[allow(dead_code)]
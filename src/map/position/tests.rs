@@ -1,5 +1,6 @@
 use super::*;
 use crate::{fov, map::chunks::generate_mesh_of_chunks};
+use std::collections::HashSet;
 use std::f32::consts::PI;
 use test::Bencher;
 
@@ -136,6 +137,37 @@ fn line_to() {
     );
 }
 
+#[test]
+fn paths_change_of_layer_only_through_ramps() {
+    use crate::{find_path, CurrentMap, Orientation, TileType};
+    use bevy::prelude::Entity;
+
+    // A floor next to a step one layer higher
+    let mut grid = CurrentMap::default();
+    let mut add_tile = |pos: Position, tile_type: TileType| {
+        grid.tiles.insert(pos, Entity::PLACEHOLDER);
+        grid.tile_types.insert(pos, tile_type);
+    };
+    add_tile(position(0, 0, 0), TileType::Floor);
+    add_tile(position(1, 0, 0), TileType::Block);
+    add_tile(position(1, 0, 1), TileType::Floor);
+    grid.blocked_coords.insert(position(1, 0, 0));
+    assert_eq!(find_path(&position(0, 0, 0), &position(1, 0, 1), &grid), None);
+
+    grid.tile_types.insert(position(0, 0, 0), TileType::Ramp(Orientation::TopRight));
+    assert!(find_path(&position(0, 0, 0), &position(1, 0, 1), &grid).is_some());
+    assert!(find_path(&position(1, 0, 1), &position(0, 0, 0), &grid).is_some());
+
+    // The ramp only climbs towards the side it rises to
+    grid.tile_types.insert(position(0, 0, 0), TileType::Ramp(Orientation::TopLeft));
+    assert_eq!(find_path(&position(0, 0, 0), &position(1, 0, 1), &grid), None);
+
+    // Or from the other side of the step, rising towards -x
+    grid.tiles.insert(position(2, 0, 0), Entity::PLACEHOLDER);
+    grid.tile_types.insert(position(2, 0, 0), TileType::Ramp(Orientation::BottomLeft));
+    assert!(find_path(&position(2, 0, 0), &position(1, 0, 1), &grid).is_some());
+}
+
 #[test]
 fn ring() {
     // Zero
@@ -0,0 +1,137 @@
+use super::CHUNK_DIMENSIONS;
+use crate::{WorldCreationState, WorldGenParams, WorldMap};
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, futures::check_ready};
+use bevy::ui::RelativeCursorPosition;
+
+/// Side of the preview in the screen, in pixels
+const PREVIEW_SIDE: f32 = 320.0;
+
+pub struct MapPreviewPlugin;
+
+impl Plugin for MapPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapPreview>()
+            .add_systems(
+                Update,
+                (
+                    start_preview_system,
+                    finish_preview_system,
+                    embark_pick_system,
+                    embark_marker_system,
+                )
+                    .run_if(in_state(WorldCreationState::MapSettings)),
+            )
+            .add_systems(OnExit(WorldCreationState::MapSettings), clear_preview);
+    }
+}
+
+/// Marker of the image showing the preview of the world
+#[derive(Component)]
+struct PreviewImage;
+
+/// Marker of the frame showing the embark chunk over the preview
+#[derive(Component)]
+struct EmbarkMarker;
+
+/// Generation of the preview running in the background
+#[derive(Resource, Default)]
+struct MapPreview {
+    /// Seed, size and parameters of the last preview started
+    generated: Option<(u32, (usize, usize), WorldGenParams)>,
+    task: Option<Task<Image>>,
+}
+
+/// Spawns the preview of the world, it can be clicked to choose the embark chunk
+pub fn spawn_preview(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(PREVIEW_SIDE),
+                height: Val::Px(PREVIEW_SIDE),
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            ImageNode::default(),
+            RelativeCursorPosition::default(),
+            PreviewImage,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BorderColor::all(Color::WHITE),
+                EmbarkMarker,
+            ));
+        });
+}
+
+// This system starts a new preview in the background when the world settings change
+fn start_preview_system(world_map: Res<WorldMap>, mut preview: ResMut<MapPreview>) {
+    let settings = (world_map.seed, world_map.size, world_map.params.clone());
+    if preview.generated.as_ref() == Some(&settings) {
+        return;
+    }
+    preview.generated = Some(settings);
+
+    // Starting a new task drops the previous one, which cancels it
+    let world_map = world_map.clone();
+    preview.task = Some(AsyncComputeTaskPool::get().spawn(async move { world_map.generate().biome_image() }));
+}
+
+// This system shows the preview once it is generated
+fn finish_preview_system(
+    mut preview: ResMut<MapPreview>,
+    mut images: ResMut<Assets<Image>>,
+    mut preview_image: Single<&mut ImageNode, With<PreviewImage>>,
+) {
+    let Some(task) = preview.task.as_mut() else { return };
+    if let Some(image) = check_ready(task) {
+        preview_image.image = images.add(image);
+        preview.task = None;
+    }
+}
+
+// This system chooses the embark chunk under the cursor when the preview is clicked
+fn embark_pick_system(
+    preview_query: Query<(&Interaction, &RelativeCursorPosition), (Changed<Interaction>, With<PreviewImage>)>,
+    mut world_map: ResMut<WorldMap>,
+) {
+    for (interaction, cursor) in &preview_query {
+        let (Interaction::Pressed, Some(normalized)) = (interaction, cursor.normalized) else {
+            continue;
+        };
+        // (-0.5, -0.5) is the top-left corner, the rows of the preview go from the top of the map
+        let size = Vec2::new(world_map.size.0 as f32, world_map.size.1 as f32);
+        let pixel = ((normalized + Vec2::splat(0.5)) * size).as_ivec2();
+        let (chunk_width, chunk_height, _) = CHUNK_DIMENSIONS;
+        let max = size.as_ivec2() / IVec2::new(chunk_width, chunk_height) - IVec2::ONE;
+        world_map.embark = (
+            (pixel.x / chunk_width).clamp(0, max.x),
+            ((size.y as i32 - 1 - pixel.y) / chunk_height).clamp(0, max.y),
+        );
+    }
+}
+
+// This system places the frame of the embark chunk over the preview
+fn embark_marker_system(world_map: Res<WorldMap>, mut marker: Single<&mut Node, With<EmbarkMarker>>) {
+    let (chunk_width, chunk_height, _) = CHUNK_DIMENSIONS;
+    let (width, height) = (world_map.size.0 as f32, world_map.size.1 as f32);
+    let (x, y) = world_map.embark;
+    let node = Node {
+        left: Val::Percent((x * chunk_width) as f32 / width * 100.0),
+        top: Val::Percent((height - ((y + 1) * chunk_height) as f32) / height * 100.0),
+        width: Val::Percent(chunk_width as f32 / width * 100.0),
+        height: Val::Percent(chunk_height as f32 / height * 100.0),
+        ..marker.clone()
+    };
+    marker.set_if_neq(node);
+}
+
+fn clear_preview(mut preview: ResMut<MapPreview>) {
+    *preview = MapPreview::default();
+}
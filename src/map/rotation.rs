@@ -0,0 +1,64 @@
+use super::streaming::SURFACE_MIDDLE;
+use crate::{CurrentMap, GameState, Position};
+use bevy::prelude::*;
+use std::ops::Neg;
+
+/// Sent when the view is turned, once the layout of the [`CurrentMap`] has its new rotation
+#[derive(Message)]
+pub(super) struct ViewRotated;
+
+pub struct ViewRotationPlugin;
+
+impl Plugin for ViewRotationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ViewRotated>().add_systems(
+            Update,
+            (rotate_view, place_sprites.after(rotate_view)).run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+/// Turns the world a quarter of a turn counter-clockwise with Q and clockwise with E, the camera
+/// stays over the same tile
+pub(super) fn rotate_view(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut camera: Single<&mut Transform, With<Camera>>,
+    mut current_map: ResMut<CurrentMap>,
+    mut rotated: MessageWriter<ViewRotated>,
+) {
+    let rotation = current_map.layout.rotation;
+    let rotation = if keyboard_input.just_pressed(KeyCode::KeyQ) {
+        rotation.counter_clockwise()
+    } else if keyboard_input.just_pressed(KeyCode::KeyE) {
+        rotation.clockwise()
+    } else {
+        return;
+    };
+
+    let center = current_map
+        .layout
+        .world_pos_to_tile(camera.translation.truncate(), SURFACE_MIDDLE);
+    current_map.layout.rotate(rotation);
+    let world_pos = current_map.layout.tile_to_world_pos(center);
+    camera.translation.x = world_pos.x;
+    camera.translation.y = world_pos.y;
+    rotated.write(ViewRotated);
+}
+
+/// Moves the sprites of the creatures, items and highlights to where their position is drawn from
+/// the new side, and sorts their depth again
+fn place_sprites(
+    mut rotated: MessageReader<ViewRotated>,
+    current_map: Res<CurrentMap>,
+    mut sprites_query: Query<(&Position, &mut Transform), With<Sprite>>,
+) {
+    if rotated.read().count() == 0 {
+        return;
+    }
+    for (pos, mut transform) in &mut sprites_query {
+        // Each kind of sprite is drawn a little over the tile of its position, that is kept
+        let offset = transform.translation.z - (transform.translation.y.neg() / 100.0 + pos.z as f32);
+        let coord = current_map.layout.tile_to_world_pos(*pos);
+        transform.translation = Vec3::new(coord.x, coord.y, coord.y.neg() / 100.0 + coord.z + offset);
+    }
+}
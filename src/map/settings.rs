@@ -1,11 +1,38 @@
-use crate::WorldCreationState;
+// This lint usually gives bad advice in the context of Bevy -- hiding complex queries behind
+// type aliases tends to obfuscate code while offering no improvement in code cleanliness.
+#![allow(clippy::type_complexity)]
+
+use super::export::EXPORTED_MAP_FILE;
+use super::import::{MapImportFile, TMX_MAP_FILE};
+use super::preview::spawn_preview;
+use crate::menus::{NORMAL_BUTTON, PRESSED_BUTTON, SelectedOption, TEXT_COLOR};
+use crate::{WorldCreationState, WorldGenParams, WorldMap};
+use bevy::color::palettes::css::CRIMSON;
+use bevy::input::{ButtonState, keyboard::Key, keyboard::KeyboardInput};
 use bevy::prelude::*;
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_PRESET_FILE: &str = "./data/worldgen/default.ron";
+const PRESETS_DIR: &str = "./data/worldgen";
+
+/// Sizes of the map the player can choose, in noise values
+const MAP_SIZES: [(&str, (usize, usize)); 3] = [("Small", (32, 32)), ("Medium", (64, 64)), ("Large", (128, 128))];
+
+/// Longest seed that can be typed
+const MAX_SEED_LENGTH: usize = 20;
 
 pub struct MapSettingsPlugin;
 
 impl Plugin for MapSettingsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(WorldCreationState::MapSettings), map_settings_startup)
+        app.init_resource::<SeedInput>()
+            .init_resource::<WorldGenPresets>()
+            .add_systems(OnEnter(WorldCreationState::MapSettings), map_settings_startup)
+            .add_systems(
+                Update,
+                (map_settings_action, seed_input_system).run_if(in_state(WorldCreationState::MapSettings)),
+            )
             .add_systems(
                 OnExit(WorldCreationState::MapSettings),
                 crate::despawn_screen::<MapSettingsScreen>,
@@ -16,6 +43,303 @@ impl Plugin for MapSettingsPlugin {
 #[derive(Component)]
 struct MapSettingsScreen;
 
-fn map_settings_startup(mut map_creation_state: ResMut<NextState<WorldCreationState>>) {
-    map_creation_state.set(WorldCreationState::MapGeneration);
+/// Marker of the text showing the seed typed by the player
+#[derive(Component)]
+struct SeedText;
+
+/// Text typed in the seed field
+#[derive(Resource, Default)]
+struct SeedInput(String);
+
+/// World generation presets found in [`PRESETS_DIR`] by file name
+#[derive(Resource, Default)]
+struct WorldGenPresets(Vec<(String, WorldGenParams)>);
+
+// All actions that can be triggered from a button of the map settings screen
+#[derive(Component)]
+enum MapSettingsButtonAction {
+    Size((usize, usize)),
+    Preset(usize),
+    Randomize,
+    Generate,
+    /// Plays the map of the TMX file instead of a generated one
+    LoadMap(&'static str),
+}
+
+fn map_settings_startup(
+    mut commands: Commands,
+    mut world_map: ResMut<WorldMap>,
+    mut seed_input: ResMut<SeedInput>,
+    mut presets: ResMut<WorldGenPresets>,
+) {
+    match WorldGenParams::load(DEFAULT_PRESET_FILE) {
+        Ok(params) => world_map.params = params,
+        Err(err) => warn!(
+            "Unable to load the world generation preset {}: {}",
+            DEFAULT_PRESET_FILE, err
+        ),
+    }
+    presets.0 = load_presets();
+    seed_input.0 = world_map.seed.to_string();
+
+    let button_node = Node {
+        width: Val::Px(200.0),
+        height: Val::Px(50.0),
+        margin: UiRect::all(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = (
+        TextFont {
+            font_size: 30.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+    let row_node = Node {
+        align_items: AlignItems::Center,
+        flex_wrap: FlexWrap::Wrap,
+        justify_content: JustifyContent::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            MapSettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(CRIMSON.into()),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("World Settings"),
+                        TextFont {
+                            font_size: 50.0,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..default()
+                        },
+                    ));
+
+                    spawn_preview(parent);
+
+                    // Seed, typed with the keyboard or randomized
+                    parent.spawn(row_node.clone()).with_children(|parent| {
+                        parent.spawn((Text::new("Seed"), button_text_style.clone()));
+                        parent
+                            .spawn((
+                                Node {
+                                    width: Val::Px(300.0),
+                                    height: Val::Px(50.0),
+                                    margin: UiRect::all(Val::Px(10.0)),
+                                    padding: UiRect::horizontal(Val::Px(10.0)),
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BackgroundColor(NORMAL_BUTTON),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((Text::new(seed_input.0.clone()), button_text_style.clone(), SeedText));
+                            });
+                        parent
+                            .spawn((
+                                Button,
+                                button_node.clone(),
+                                BackgroundColor(NORMAL_BUTTON),
+                                MapSettingsButtonAction::Randomize,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((Text::new("Randomize"), button_text_style.clone()));
+                            });
+                    });
+
+                    // Map size
+                    parent.spawn(row_node.clone()).with_children(|parent| {
+                        parent.spawn((Text::new("Size"), button_text_style.clone()));
+                        for (name, size) in MAP_SIZES {
+                            let mut entity = parent.spawn((
+                                Button,
+                                button_node.clone(),
+                                BackgroundColor(NORMAL_BUTTON),
+                                MapSettingsButtonAction::Size(size),
+                            ));
+                            entity.with_children(|parent| {
+                                parent.spawn((Text::new(name), button_text_style.clone()));
+                            });
+                            if world_map.size == size {
+                                entity.insert((SelectedOption, BackgroundColor(PRESSED_BUTTON)));
+                            }
+                        }
+                    });
+
+                    // World generation presets
+                    parent.spawn(row_node.clone()).with_children(|parent| {
+                        parent.spawn((Text::new("Preset"), button_text_style.clone()));
+                        for (i, (name, params)) in presets.0.iter().enumerate() {
+                            let mut entity = parent.spawn((
+                                Button,
+                                button_node.clone(),
+                                BackgroundColor(NORMAL_BUTTON),
+                                MapSettingsButtonAction::Preset(i),
+                            ));
+                            entity.with_children(|parent| {
+                                parent.spawn((Text::new(name.clone()), button_text_style.clone()));
+                            });
+                            if world_map.params == *params {
+                                entity.insert((SelectedOption, BackgroundColor(PRESSED_BUTTON)));
+                            }
+                        }
+                    });
+
+                    // Generate the world, or play the hand-authored map or the exported one instead
+                    parent.spawn(row_node.clone()).with_children(|parent| {
+                        let mut actions = vec![
+                            (MapSettingsButtonAction::Generate, "Generate"),
+                            (MapSettingsButtonAction::LoadMap(TMX_MAP_FILE), "Load Map"),
+                        ];
+                        if Path::new(EXPORTED_MAP_FILE).exists() {
+                            actions.push((MapSettingsButtonAction::LoadMap(EXPORTED_MAP_FILE), "Load Export"));
+                        }
+                        for (action, text) in actions {
+                            parent
+                                .spawn((Button, button_node.clone(), BackgroundColor(NORMAL_BUTTON), action))
+                                .with_children(|parent| {
+                                    parent.spawn((Text::new(text), button_text_style.clone()));
+                                });
+                        }
+                    });
+                });
+        });
+}
+
+/// Loads every preset of [`PRESETS_DIR`] sorted by name, the broken ones are skipped
+fn load_presets() -> Vec<(String, WorldGenParams)> {
+    let Ok(entries) = fs::read_dir(PRESETS_DIR) else {
+        warn!("Unable to read the world generation presets in {}", PRESETS_DIR);
+        return Vec::new();
+    };
+    let mut presets: Vec<(String, WorldGenParams)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            match WorldGenParams::load(&path) {
+                Ok(params) => Some((name, params)),
+                Err(err) => {
+                    warn!("Unable to load the world generation preset {}: {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .collect();
+    presets.sort_by(|(a, _), (b, _)| a.cmp(b));
+    presets
+}
+
+/// Seed of the typed text, numbers are used as they are and any other text is hashed with FNV-1a
+/// so the same text always gives the same world
+fn parse_seed(text: &str) -> u32 {
+    text.trim().parse().unwrap_or_else(|_| {
+        text.bytes().fold(0x811c_9dc5, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        })
+    })
+}
+
+// This system writes the typed seed into the `WorldMap`
+fn seed_input_system(
+    mut keyboard_events: MessageReader<KeyboardInput>,
+    mut seed_input: ResMut<SeedInput>,
+    mut world_map: ResMut<WorldMap>,
+    mut seed_text: Single<&mut Text, With<SeedText>>,
+) {
+    let mut changed = false;
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match (&event.logical_key, &event.text) {
+            (Key::Backspace, _) => {
+                seed_input.0.pop();
+            }
+            (_, Some(text)) if seed_input.0.len() < MAX_SEED_LENGTH => {
+                seed_input.0.extend(text.chars().filter(|c| !c.is_control()));
+            }
+            _ => continue,
+        }
+        changed = true;
+    }
+    if changed {
+        world_map.seed = parse_seed(&seed_input.0);
+        seed_text.0 = seed_input.0.clone();
+    }
+}
+
+// This system applies the button pressed to the `WorldMap`, and marks the chosen options as selected
+fn map_settings_action(
+    interaction_query: Query<(&Interaction, &MapSettingsButtonAction), (Changed<Interaction>, With<Button>)>,
+    mut options_query: Query<(Entity, &MapSettingsButtonAction, &mut BackgroundColor)>,
+    mut commands: Commands,
+    mut world_map: ResMut<WorldMap>,
+    mut seed_input: ResMut<SeedInput>,
+    mut seed_text: Single<&mut Text, With<SeedText>>,
+    presets: Res<WorldGenPresets>,
+    mut map_creation_state: ResMut<NextState<WorldCreationState>>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            MapSettingsButtonAction::Size(size) => {
+                world_map.size = *size;
+                world_map.embark = world_map.center_chunk();
+            }
+            MapSettingsButtonAction::Preset(i) => world_map.params = presets.0[*i].1.clone(),
+            MapSettingsButtonAction::Randomize => {
+                world_map.seed = rand::random();
+                seed_input.0 = world_map.seed.to_string();
+                seed_text.0 = seed_input.0.clone();
+            }
+            MapSettingsButtonAction::Generate => map_creation_state.set(WorldCreationState::MapGeneration),
+            MapSettingsButtonAction::LoadMap(file) => {
+                commands.insert_resource(MapImportFile(file));
+                map_creation_state.set(WorldCreationState::MapImport);
+            }
+        }
+
+        for (entity, option, mut color) in &mut options_query {
+            let selected = match option {
+                MapSettingsButtonAction::Size(size) => world_map.size == *size,
+                MapSettingsButtonAction::Preset(i) => world_map.params == presets.0[*i].1,
+                _ => continue,
+            };
+            if selected {
+                commands.entity(entity).insert(SelectedOption);
+                *color = PRESSED_BUTTON.into();
+            } else {
+                commands.entity(entity).remove::<SelectedOption>();
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
 }
@@ -0,0 +1,320 @@
+use super::cutaway::change_view_level;
+use super::placement::place_scattered;
+use super::rotation::rotate_view;
+use super::*;
+use crate::{CurrentMap, GameState, RawMaster, SpawnType, WorldChunks, WorldMap, spawner::SpawnEntity};
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Size in world/pixel coordinates of the screen rectangle covered by a [`Chunk`]
+const CHUNK_SIZE: Vec2 = Vec2::new(
+    TILE_SIZE.x * CHUNK_DIMENSIONS.0 as f32,
+    TILE_SIZE.y * (CHUNK_DIMENSIONS.1 as f32 / 4.0),
+);
+
+/// Size of a [`Chunk`] on the screen when the view is turned sideways, the columns of chunks go
+/// down the screen instead of across it
+const SIDEWAYS_CHUNK_SIZE: Vec2 = Vec2::new(
+    TILE_SIZE.x * (CHUNK_DIMENSIONS.1 as f32 / 2.0),
+    TILE_SIZE.y * (CHUNK_DIMENSIONS.0 as f32 / 2.0),
+);
+
+/// Number of chunks kept spawned around the ones the camera is looking at
+const STREAMING_MARGIN: i32 = 1;
+
+/// Layer in the middle of the heights the terrain surface can take
+pub(super) const SURFACE_MIDDLE: i32 = (UNDERGROUND_LAYERS + CHUNK_DIMENSIONS.2) / 2;
+
+/// Height in world/pixel coordinates of half of the heights the terrain surface can take
+const SURFACE_HEIGHT: f32 = (CHUNK_DIMENSIONS.2 - SURFACE_MIDDLE) as f32 * TILE_SIZE.y / 2.0;
+
+/// Maximum number of new chunks sampled from the planet noise in a single frame
+const MAX_GENERATED_CHUNKS_PER_FRAME: usize = 4;
+
+pub struct ChunkStreamingPlugin;
+
+impl Plugin for ChunkStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (cull_loaded_chunks, chunk_streaming_system)
+                .chain()
+                .after(rotate_view)
+                .after(change_view_level)
+                .run_if(in_state(GameState::InGame)),
+        )
+        .add_systems(OnExit(GameState::InGame), clear_loaded_chunks);
+    }
+}
+
+/// Spawns the tiles of the chunks entering the camera radius and despawns the ones leaving it
+fn chunk_streaming_system(
+    camera: Single<(&Transform, &Projection), With<Camera>>,
+    mut commands: Commands,
+    mut spawn_event: MessageWriter<SpawnEntity>,
+    mut world_chunks: ResMut<WorldChunks>,
+    world_map: Res<WorldMap>,
+    raw_master: Res<RawMaster>,
+    mut current_map: ResMut<CurrentMap>,
+) {
+    let (transform, projection) = camera.into_inner();
+    let Projection::Orthographic(orthographic) = projection else { return };
+    let rotation = current_map.layout.rotation;
+
+    // The radius covers the camera view, chunks are rectangles in screen space, and the terrain
+    // surface that can be drawn over or under the middle of it
+    let chunk_size = if rotation.is_sideways() { SIDEWAYS_CHUNK_SIZE } else { CHUNK_SIZE };
+    let surface_chunks = (SURFACE_HEIGHT / chunk_size.y) as i32 + 1;
+    let mut radius = (orthographic.area.half_size() / chunk_size).ceil().as_ivec2()
+        + IVec2::new(STREAMING_MARGIN, STREAMING_MARGIN + surface_chunks);
+    // Turned sideways, the columns of chunks go down the screen
+    if rotation.is_sideways() {
+        radius = radius.yx();
+    }
+    let (center_x, center_y) = current_map
+        .layout
+        .world_pos_to_tile(transform.translation.truncate(), SURFACE_MIDDLE)
+        .chunk();
+    let in_range: HashSet<(i32, i32)> = (center_x - radius.x..=center_x + radius.x)
+        .flat_map(|x| (center_y - radius.y..=center_y + radius.y).map(move |y| (x, y)))
+        .collect();
+
+    // Chunks to load, the ones out of a bounded map never are. Their tiles are culled against the
+    // chunks in front of them, which are needed as well
+    let unloaded: Vec<(i32, i32)> = in_range
+        .iter()
+        .filter(|coords| !world_chunks.loaded.contains(coords))
+        .filter(|coords| !world_chunks.bounded || world_chunks.chunks.contains_key(coords))
+        .copied()
+        .collect();
+    let fronts: HashMap<(i32, i32), HashSet<(i32, i32)>> = unloaded
+        .iter()
+        .map(|coords| (*coords, front_chunks(*coords, rotation)))
+        .collect();
+
+    // The world has no edges, chunks never seen before are sampled from the planet noise
+    let missing: HashSet<(i32, i32)> = unloaded
+        .iter()
+        .chain(fronts.values().flatten())
+        .filter(|coords| !world_chunks.bounded && !world_chunks.chunks.contains_key(coords))
+        .copied()
+        .collect();
+    let missing: Vec<(i32, i32)> = missing.into_iter().take(MAX_GENERATED_CHUNKS_PER_FRAME).collect();
+    let caves = world_map.caves();
+    for coords in missing {
+        let mut chunk = Chunk::new(coords.0, coords.1);
+        chunk.shape_terrain(&world_map.generate_chunk(coords), (0, 0), &caves);
+        place_scattered(&chunk, world_map.seed, &raw_master, &mut world_chunks, &mut spawn_event);
+        world_chunks.chunks.insert(coords, chunk);
+    }
+    // Despawn the chunks out of range, their data stays in `WorldChunks`
+    let to_unload: Vec<(i32, i32)> = world_chunks.loaded.difference(&in_range).copied().collect();
+    for coords in to_unload {
+        for (_, entity) in current_map.tiles.remove_chunk(coords) {
+            commands.entity(entity).despawn();
+        }
+        let decorations = world_chunks.decorations.get(&coords).map_or(&[][..], Vec::as_slice);
+        current_map.remove_chunk(&world_chunks.chunks[&coords], decorations);
+        world_chunks.loaded.remove(&coords);
+    }
+
+    // Spawn the chunks that came into range once the chunks in front of them are generated, their
+    // border tiles are not culled again when those come later
+    let generated = |coords: &(i32, i32)| world_chunks.chunks.contains_key(coords);
+    let to_load: Vec<(i32, i32)> = unloaded
+        .into_iter()
+        .filter(|coords| generated(coords) && (world_chunks.bounded || fronts[coords].iter().all(generated)))
+        .collect();
+    for coords in to_load {
+        // The chunks out of a bounded map are empty
+        let is_block = |pos: &Position| {
+            world_chunks
+                .chunks
+                .get(&pos.chunk())
+                .is_some_and(|chunk| chunk.is_block(pos))
+        };
+        let chunk = &world_chunks.chunks[&coords];
+        for tile in get_visible_tiles(chunk, rotation, current_map.layout.view_level, is_block) {
+            spawn_event.write(SpawnEntity {
+                name: chunk.tile_name(&tile, &raw_master),
+                pos: SpawnType::AtPosition {
+                    x: tile.pos.x,
+                    y: tile.pos.y,
+                    z: tile.pos.z,
+                },
+            });
+        }
+        for (name, pos) in world_chunks.decorations.get(&coords).into_iter().flatten() {
+            spawn_event.write(SpawnEntity {
+                name: name.clone(),
+                pos: SpawnType::AtPosition {
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                },
+            });
+        }
+        // Every tile of the chunk is walked and seen through, the culled ones as well
+        let decorations = world_chunks.decorations.get(&coords).map_or(&[][..], Vec::as_slice);
+        current_map.add_chunk(chunk, decorations, &raw_master);
+        world_chunks.loaded.insert(coords);
+    }
+}
+
+/// Culls the loaded chunks again when the view is turned or cut at another level. Only the tiles
+/// hidden or shown by it are despawned or spawned, the way and the sight through the map do not
+/// change with the view
+fn cull_loaded_chunks(
+    mut commands: Commands,
+    mut spawn_event: MessageWriter<SpawnEntity>,
+    world_chunks: Res<WorldChunks>,
+    raw_master: Res<RawMaster>,
+    mut current_map: ResMut<CurrentMap>,
+    names_query: Query<&Name>,
+    mut culled_view: Local<(ViewRotation, Option<i32>)>,
+) {
+    let view = (current_map.layout.rotation, current_map.layout.view_level);
+    if *culled_view == view {
+        return;
+    }
+    *culled_view = view;
+
+    let is_block = |pos: &Position| {
+        world_chunks
+            .chunks
+            .get(&pos.chunk())
+            .is_some_and(|chunk| chunk.is_block(pos))
+    };
+    for coords in &world_chunks.loaded {
+        let chunk = &world_chunks.chunks[coords];
+        let mut shown: HashMap<Position, String> = get_visible_tiles(chunk, view.0, view.1, is_block)
+            .into_iter()
+            .map(|tile| (tile.pos, chunk.tile_name(&tile, &raw_master)))
+            .collect();
+        // The decorations sharing the position of a tile stay
+        let spawned: Vec<(Position, Entity)> = current_map.tiles.in_chunk(*coords).collect();
+        for (pos, entity) in spawned {
+            let Some(tile_type) = chunk.get_tile(&pos) else { continue };
+            let name = chunk.tile_name(&TileData { pos, tile_type }, &raw_master);
+            if names_query.get(entity).is_ok_and(|spawned| spawned.as_str() != name) {
+                continue;
+            }
+            if shown.remove(&pos).is_some() {
+                continue;
+            }
+            commands.entity(entity).despawn();
+            current_map.tiles.remove(&pos, entity);
+        }
+        for (pos, name) in shown {
+            spawn_event.write(SpawnEntity {
+                name,
+                pos: SpawnType::AtPosition {
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                },
+            });
+        }
+    }
+}
+
+/// Every tile, creature and item is despawned when leaving the game, so no chunk is loaded anymore
+fn clear_loaded_chunks(mut world_chunks: ResMut<WorldChunks>, mut current_map: ResMut<CurrentMap>) {
+    world_chunks.loaded.clear();
+    current_map.tiles = default();
+    current_map.entities = default();
+    current_map.items = default();
+    current_map.blocked_coords.clear();
+    current_map.tile_types.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raws::read_raws;
+    use crate::spawner::{game_world, spawn_entity};
+
+    #[test]
+    fn turning_the_view_only_respawns_the_tiles_whose_culling_changed() {
+        // A tile covered on top and on its front faces seen from the default side, but not from the
+        // opposite one
+        let mut chunk = Chunk::empty(0, 0);
+        let covered = Layer::tile_position((0, 0), 8 * CHUNK_DIMENSIONS.0 as usize + 8, 0);
+        let [left, right] = ViewRotation::Deg0.front_steps();
+        let top = covered + Position::new(0, 0, 1);
+        for pos in [covered, top, covered + left, covered + right] {
+            assert_eq!(pos.chunk(), (0, 0));
+            chunk.set_tile(&pos, TileType::Block);
+            chunk.tile_names.insert(pos, "StoneBlock".to_string());
+        }
+
+        let mut world = game_world();
+        let mut current_map = CurrentMap::default();
+        current_map.layout.rotate(ViewRotation::Deg180);
+        current_map.add_chunk(&chunk, &[], world.resource::<RawMaster>());
+        world.insert_resource(current_map);
+        let mut world_chunks = WorldChunks::default();
+        world_chunks.chunks.insert((0, 0), chunk);
+        world_chunks.loaded.insert((0, 0));
+        world.insert_resource(world_chunks);
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((cull_loaded_chunks, spawn_entity).chain());
+        schedule.run(&mut world);
+        world.flush();
+        let current_map = world.resource::<CurrentMap>();
+        assert!(current_map.tiles.contains(&covered));
+        let top_entity = current_map.tiles.get(&top)[0];
+        let (tile_types, blocked_coords) = (current_map.tile_types.clone(), current_map.blocked_coords.clone());
+
+        // Turned to the default side, the covered tile is despawned and the others never leave the map
+        world.resource_mut::<CurrentMap>().layout.rotate(ViewRotation::Deg0);
+        schedule.run(&mut world);
+        let current_map = world.resource::<CurrentMap>();
+        assert!(!current_map.tiles.contains(&covered));
+        assert_eq!(current_map.tiles.get(&top), &[top_entity]);
+        // The way and the sight through the map stay the same
+        assert_eq!(current_map.tile_types, tile_types);
+        assert_eq!(current_map.blocked_coords, blocked_coords);
+        assert_eq!(current_map.tile_types.get(&covered), Some(&TileType::Block));
+        world.flush();
+        assert!(world.get_entity(top_entity).is_ok());
+    }
+
+    #[test]
+    fn tunnels_covered_from_the_view_are_walked_through() {
+        // A bend of a tunnel hidden from the default side, under a roof and behind walls
+        let center = Layer::tile_position((0, 0), 8 * CHUNK_DIMENSIONS.0 as usize + 8, 1);
+        let tunnel = [(0, 2), (0, 1), (0, 0), (1, 0), (2, 0)].map(|(x, y)| center + Position::new(x, y, 0));
+        let mut chunk = Chunk::empty(0, 0);
+        for x in -1..=3 {
+            for y in -1..=3 {
+                for z in 0..=2 {
+                    let pos = center + Position::new(x, y, z - 1);
+                    assert_eq!(pos.chunk(), (0, 0));
+                    let (tile_type, name) = if z == 1 && tunnel.contains(&pos) {
+                        (TileType::Floor, "StoneFloor")
+                    } else {
+                        (TileType::Block, "StoneBlock")
+                    };
+                    chunk.set_tile(&pos, tile_type);
+                    chunk.tile_names.insert(pos, name.to_string());
+                }
+            }
+        }
+        let bend = tunnel[2];
+        let visible = get_visible_tiles(&chunk, ViewRotation::Deg0, None, |_| false);
+        assert!(!visible.iter().any(|tile| tile.pos == bend));
+
+        let mut raw_master = RawMaster {
+            raws: read_raws(),
+            ..default()
+        };
+        raw_master.load();
+        let mut current_map = CurrentMap::default();
+        current_map.add_chunk(&chunk, &[], &raw_master);
+        let path = find_path(&tunnel[0], &tunnel[4], &current_map).unwrap();
+        assert!(path.contains(&bend));
+        assert!(!current_map.blocks_sight(bend));
+    }
+}
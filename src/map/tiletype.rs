@@ -1,16 +1,113 @@
-#[derive(PartialEq, Eq, Debug, Clone)]
+use crate::Position;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Hash)]
 pub enum TileType {
     Block,
     Floor,
+    /// Slope climbing to the next layer
+    Ramp(Orientation),
+    /// Steps climbing to the next layer
+    Stair(Orientation),
+    HalfBlock,
+    QuarterBlock,
+    /// Water a creature can wade through
+    ShallowWater,
+    /// Water too deep to walk through
+    DeepWater,
+    /// Water of a river, it can be waded through against its current
+    FlowingWater,
+}
+
+/// Side of the screen a ramp or stair rises to
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum Orientation {
+    /// Rises towards +y
+    TopLeft,
+    /// Rises towards +x
+    TopRight,
+    /// Rises towards -y
+    BottomRight,
+    /// Rises towards -x
+    BottomLeft,
+}
+
+impl Orientation {
+    pub const ALL: [Self; 4] = [Self::TopLeft, Self::TopRight, Self::BottomRight, Self::BottomLeft];
+
+    /// Step on the same layer towards the side the tile rises to
+    #[must_use]
+    pub fn step(self) -> Position {
+        match self {
+            Self::TopLeft => Position::new(0, 1, 0),
+            Self::TopRight => Position::new(1, 0, 0),
+            Self::BottomRight => Position::new(0, -1, 0),
+            Self::BottomLeft => Position::new(-1, 0, 0),
+        }
+    }
+}
+
+impl TileType {
+    /// Side the tile rises to, if it climbs to the next layer
+    #[must_use]
+    pub fn climbs_to(self) -> Option<Orientation> {
+        match self {
+            Self::Ramp(orientation) | Self::Stair(orientation) => Some(orientation),
+            _ => None,
+        }
+    }
+
+    /// End of the names of the tile raws of this type, they start by the name of their material but
+    /// the water ones, which are named only by it
+    #[must_use]
+    pub fn raw_suffix(self) -> &'static str {
+        match self {
+            Self::Block => "Block",
+            Self::Floor => "Floor",
+            Self::Ramp(Orientation::TopLeft) => "RampTopLeft",
+            Self::Ramp(Orientation::TopRight) => "RampTopRight",
+            Self::Stair(Orientation::TopLeft) => "StairTopLeft",
+            Self::Ramp(Orientation::BottomRight) => "RampBottomRight",
+            Self::Ramp(Orientation::BottomLeft) => "RampBottomLeft",
+            Self::Stair(Orientation::TopRight) => "StairTopRight",
+            Self::Stair(Orientation::BottomRight) => "StairBottomRight",
+            Self::Stair(Orientation::BottomLeft) => "StairBottomLeft",
+            Self::HalfBlock => "HalfBlock",
+            Self::QuarterBlock => "QuarterBlock",
+            Self::ShallowWater => "ShallowWater",
+            Self::DeepWater => "DeepWater",
+            Self::FlowingWater => "FlowingWater",
+        }
+    }
+
+    #[must_use]
+    pub fn is_water(self) -> bool {
+        matches!(self, Self::ShallowWater | Self::DeepWater | Self::FlowingWater)
+    }
 }
 
 pub fn tile_walkable(tiletype: TileType) -> bool {
     match tiletype {
-        TileType::Floor => true,
+        TileType::Floor
+        | TileType::Ramp(_)
+        | TileType::Stair(_)
+        | TileType::HalfBlock
+        | TileType::QuarterBlock
+        | TileType::ShallowWater
+        | TileType::FlowingWater => true,
         _ => false,
     }
 }
 
+/// How many times longer than over the ground it takes to walk over a tile
+pub fn tile_walk_cost(tiletype: TileType) -> u32 {
+    match tiletype {
+        TileType::ShallowWater => 2,
+        TileType::FlowingWater => 3,
+        _ => 1,
+    }
+}
+
 pub fn tile_opaque(tiletype: TileType) -> bool {
     match tiletype {
         TileType::Block => true,
@@ -0,0 +1,445 @@
+//! Maps made in [Tiled](https://www.mapeditor.org/) saved as isometric TMX files.
+//!
+//! Each tile layer is named by its z-coordinate and its data is CSV encoded. The tiles of the
+//! tilesets are matched to the tile raws by a `name` property, or else by the path of their image
+//! against the sprites of the raws. The tiles of a spritesheet tileset are the frames of its image,
+//! matched against the frames of the sprite raws.
+//!
+//! Tiled counts the columns down to the right and the rows down to the left of the screen, which
+//! are the `-y` and `-x` axes of a [`Position`], starting from the `origin_x` and `origin_y` map
+//! properties. Creatures, items and decorations are the objects of the object layers, named by their
+//! raw and with their z-coordinate in a `z` property.
+
+use super::{CHUNK_DIMENSIONS, Chunk, TILE_SIZE};
+use crate::{Position, RawMaster};
+use roxmltree::{Document, Node};
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::Write,
+    fs,
+    path::Path,
+};
+
+/// Bits of a GID that Tiled uses to flip and rotate the tile
+const GID_FLAGS: u32 = 0xF000_0000;
+
+/// A map read from a TMX file
+pub struct TmxMap {
+    /// Chunks with the tiles of the map, they keep the names of their raws in [`Chunk::tile_names`]
+    pub chunks: HashMap<(i32, i32), Chunk>,
+    /// Named raws placed on the map, like creatures, items and decorations
+    pub objects: Vec<(String, Position)>,
+}
+
+/// Reads the TMX map at `path`
+pub fn load_tmx(path: impl AsRef<Path>, raw_master: &RawMaster) -> Result<TmxMap, Box<dyn Error>> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    let document = Document::parse(&text)?;
+    let map = document.root_element();
+    if map.attribute("orientation") != Some("isometric") {
+        return Err("only isometric maps are supported".into());
+    }
+    if map.attribute("infinite") == Some("1") {
+        return Err("infinite maps are not supported".into());
+    }
+
+    let origin_x: i32 = property(map, "origin_x").map_or(Ok(0), str::parse)?;
+    let origin_y: i32 = property(map, "origin_y").map_or(Ok(0), str::parse)?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut tile_names: HashMap<u32, String> = HashMap::new();
+    for tileset in map.children().filter(|node| node.has_tag_name("tileset")) {
+        let first_gid: u32 = attribute(tileset, "firstgid")?.parse()?;
+        // External tilesets are kept in their own TSX file
+        match tileset.attribute("source") {
+            Some(source) => {
+                let source = directory.join(source);
+                let text = fs::read_to_string(&source)?;
+                let document = Document::parse(&text)?;
+                let tileset_directory = source.parent().unwrap_or(Path::new(""));
+                read_tileset(
+                    document.root_element(),
+                    first_gid,
+                    tileset_directory,
+                    raw_master,
+                    &mut tile_names,
+                )?;
+            }
+            None => read_tileset(tileset, first_gid, directory, raw_master, &mut tile_names)?,
+        }
+    }
+
+    let mut chunks: HashMap<(i32, i32), Chunk> = HashMap::new();
+    for layer in map.children().filter(|node| node.has_tag_name("layer")) {
+        let layer_name = attribute(layer, "name")?;
+        let z: i32 = layer_name
+            .parse()
+            .map_err(|_| format!("layer {} is not named by its z-coordinate", layer_name))?;
+        if !(0..CHUNK_DIMENSIONS.2).contains(&z) {
+            return Err(format!("layer {} is out of the {} layers of a chunk", z, CHUNK_DIMENSIONS.2).into());
+        }
+        let width: usize = attribute(layer, "width")?.parse()?;
+        let data = layer
+            .children()
+            .find(|node| node.has_tag_name("data"))
+            .ok_or_else(|| format!("layer {} has no data", z))?;
+        if data.attribute("encoding") != Some("csv") {
+            return Err(format!("layer {} is not CSV encoded", z).into());
+        }
+
+        let gids = data.text().unwrap_or_default().split(',').map(str::trim);
+        for (i, gid) in gids.filter(|gid| !gid.is_empty()).enumerate() {
+            let gid = gid.parse::<u32>()? & !GID_FLAGS;
+            if gid == 0 {
+                continue;
+            }
+            let name = tile_names
+                .get(&gid)
+                .ok_or_else(|| format!("tile {} of layer {} is not in any tileset", gid, z))?;
+            let (column, row) = ((i % width) as i32, (i / width) as i32);
+            let pos = Position::new(origin_x - row, origin_y - column, z);
+            let (chunk_x, chunk_y) = pos.chunk();
+            let chunk = chunks
+                .entry((chunk_x, chunk_y))
+                .or_insert_with(|| Chunk::empty(chunk_x, chunk_y));
+            chunk.set_tile(&pos, raw_master.raws.tiles[raw_master.tile_index[name]].tile_type());
+            chunk.tile_names.insert(pos, name.clone());
+        }
+    }
+
+    // The objects of isometric maps are placed in pixels of the tile height along both axes
+    let tile_height: f32 = attribute(map, "tileheight")?.parse()?;
+    let mut objects = Vec::new();
+    for object in map
+        .children()
+        .filter(|node| node.has_tag_name("objectgroup"))
+        .flat_map(|group| group.children().filter(|node| node.has_tag_name("object")))
+    {
+        let name = attribute(object, "name")?;
+        let column = (attribute(object, "x")?.parse::<f32>()? / tile_height).floor() as i32;
+        let row = (attribute(object, "y")?.parse::<f32>()? / tile_height).floor() as i32;
+        let z: i32 = property(object, "z").map_or(Ok(0), str::parse)?;
+        objects.push((name.to_string(), Position::new(origin_x - row, origin_y - column, z)));
+    }
+    Ok(TmxMap { chunks, objects })
+}
+
+/// Writes the `tiles` and `objects`, named by their raws, as a TMX map at `path`.
+///
+/// The images of the tilesets are the sprites of the raws, which are relative to the assets folder,
+/// so Tiled only finds them when the map is saved there.
+pub fn save_tmx(
+    path: impl AsRef<Path>,
+    tiles: &[(String, Position)],
+    objects: &[(String, Position)],
+    raw_master: &RawMaster,
+) -> Result<(), Box<dyn Error>> {
+    let positions = || tiles.iter().chain(objects).map(|(_, pos)| pos);
+    let (Some(min_x), Some(max_x)) = (positions().map(|pos| pos.x).min(), positions().map(|pos| pos.x).max()) else {
+        return Err("the map is empty".into());
+    };
+    let min_y = positions().map(|pos| pos.y).min().unwrap_or_default();
+    let max_y = positions().map(|pos| pos.y).max().unwrap_or_default();
+    // The map starts at the highest coordinates, see the module docs
+    let (width, height) = ((max_y - min_y + 1) as usize, (max_x - min_x + 1) as usize);
+    let cell = |pos: &Position| ((max_x - pos.x) as usize, (max_y - pos.y) as usize);
+
+    let names: Vec<&str> = tiles
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let layers: BTreeSet<i32> = tiles.iter().map(|(_, pos)| pos.z).collect();
+    let (tile_width, tile_height) = (TILE_SIZE.x, TILE_SIZE.y / 2.0);
+
+    let mut tmx = String::new();
+    writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        tmx,
+        r#"<map version="1.9" tiledversion="1.9.2" orientation="isometric" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="{}" nextobjectid="{}">"#,
+        width,
+        height,
+        tile_width,
+        tile_height,
+        layers.len() + 2,
+        objects.len() + 1
+    )?;
+    writeln!(tmx, " <properties>")?;
+    writeln!(tmx, r#"  <property name="origin_x" type="int" value="{}"/>"#, max_x)?;
+    writeln!(tmx, r#"  <property name="origin_y" type="int" value="{}"/>"#, max_y)?;
+    writeln!(tmx, " </properties>")?;
+
+    writeln!(
+        tmx,
+        r#" <tileset firstgid="1" name="tiles" tilewidth="{}" tileheight="{}" tilecount="{}" columns="0">"#,
+        TILE_SIZE.x,
+        TILE_SIZE.y,
+        names.len()
+    )?;
+    writeln!(tmx, r#"  <grid orientation="orthogonal" width="1" height="1"/>"#)?;
+    for (id, name) in names.iter().enumerate() {
+        let index = raw_master
+            .tile_index
+            .get(*name)
+            .ok_or_else(|| format!("tile {} is not in the tile raws", name))?;
+        writeln!(tmx, r#"  <tile id="{}">"#, id)?;
+        writeln!(tmx, "   <properties>")?;
+        writeln!(tmx, r#"    <property name="name" value="{}"/>"#, escape(name))?;
+        writeln!(tmx, "   </properties>")?;
+        let sprite = &raw_master.raws.tiles[*index].sprite;
+        let (image, frame) = raw_master
+            .raws
+            .sprite_image(sprite)
+            .ok_or_else(|| format!("sprite {} is not in the sprite raws", sprite))?;
+        // The frames of a sprite sheet are a part of its image
+        if let Some((x, y, width, height)) = frame {
+            writeln!(
+                tmx,
+                r#"   <image source="{}" x="{}" y="{}" width="{}" height="{}"/>"#,
+                escape(image),
+                x,
+                y,
+                width,
+                height
+            )?;
+        } else {
+            writeln!(tmx, r#"   <image source="{}"/>"#, escape(image))?;
+        }
+        writeln!(tmx, "  </tile>")?;
+    }
+    writeln!(tmx, " </tileset>")?;
+
+    // One layer by z-coordinate, moved up like the layers of the game
+    for (id, z) in layers.iter().enumerate() {
+        let mut gids = vec![0; width * height];
+        for (name, pos) in tiles.iter().filter(|(_, pos)| pos.z == *z) {
+            let (row, column) = cell(pos);
+            gids[row * width + column] = names.binary_search(&name.as_str()).unwrap_or_default() + 1;
+        }
+        writeln!(
+            tmx,
+            r#" <layer id="{}" name="{}" width="{}" height="{}" offsety="{}">"#,
+            id + 1,
+            z,
+            width,
+            height,
+            -z as f32 * tile_height
+        )?;
+        writeln!(tmx, r#"  <data encoding="csv">"#)?;
+        let rows: Vec<String> = gids
+            .chunks(width)
+            .map(|row| row.iter().map(ToString::to_string).collect::<Vec<_>>().join(","))
+            .collect();
+        writeln!(tmx, "{}", rows.join(",\n"))?;
+        writeln!(tmx, "</data>")?;
+        writeln!(tmx, " </layer>")?;
+    }
+
+    writeln!(tmx, r#" <objectgroup id="{}" name="objects">"#, layers.len() + 1)?;
+    for (id, (name, pos)) in objects.iter().enumerate() {
+        let (row, column) = cell(pos);
+        writeln!(
+            tmx,
+            r#"  <object id="{}" name="{}" x="{}" y="{}">"#,
+            id + 1,
+            escape(name),
+            (column as f32 + 0.5) * tile_height,
+            (row as f32 + 0.5) * tile_height
+        )?;
+        writeln!(tmx, "   <properties>")?;
+        writeln!(tmx, r#"    <property name="z" type="int" value="{}"/>"#, pos.z)?;
+        writeln!(tmx, "   </properties>")?;
+        writeln!(tmx, "   <point/>")?;
+        writeln!(tmx, "  </object>")?;
+    }
+    writeln!(tmx, " </objectgroup>")?;
+    writeln!(tmx, "</map>")?;
+
+    fs::write(path, tmx)?;
+    Ok(())
+}
+
+/// Matches the tiles of a tileset to the tile raws by their GID
+fn read_tileset(
+    tileset: Node,
+    first_gid: u32,
+    directory: &Path,
+    raw_master: &RawMaster,
+    tile_names: &mut HashMap<u32, String>,
+) -> Result<(), Box<dyn Error>> {
+    for tile in tileset.children().filter(|node| node.has_tag_name("tile")) {
+        let id: u32 = attribute(tile, "id")?.parse()?;
+        let name_property = property(tile, "name");
+        let image = tile
+            .children()
+            .find(|node| node.has_tag_name("image"))
+            .and_then(|image| image.attribute("source"))
+            .map(|source| directory.join(source));
+
+        let name = match (name_property, image) {
+            (Some(name), _) if raw_master.tile_index.contains_key(name) => name.to_string(),
+            (Some(name), _) => return Err(format!("tile {} is not in the tile raws", name).into()),
+            (None, Some(image)) => sprite_tile_name(&image, None, raw_master)
+                .ok_or_else(|| format!("no tile raw has the sprite {}", image.display()))?,
+            (None, None) => continue,
+        };
+        tile_names.insert(first_gid + id, name);
+    }
+
+    // A spritesheet tileset has one image cut into its tiles, the ones left without a name are
+    // matched by their frame. Those matching no raw are only an error if the map uses them
+    let Some(sheet) = tileset.children().find(|node| node.has_tag_name("image")) else {
+        return Ok(());
+    };
+    let image = directory.join(attribute(sheet, "source")?);
+    let tile_width: u32 = attribute(tileset, "tilewidth")?.parse()?;
+    let tile_height: u32 = attribute(tileset, "tileheight")?.parse()?;
+    let tile_count: u32 = attribute(tileset, "tilecount")?.parse()?;
+    let columns: u32 = attribute(tileset, "columns")?.parse()?;
+    let margin: u32 = tileset.attribute("margin").map_or(Ok(0), str::parse)?;
+    let spacing: u32 = tileset.attribute("spacing").map_or(Ok(0), str::parse)?;
+    for id in 0..tile_count {
+        if tile_names.contains_key(&(first_gid + id)) {
+            continue;
+        }
+        let frame = (
+            margin + id % columns.max(1) * (tile_width + spacing),
+            margin + id / columns.max(1) * (tile_height + spacing),
+            tile_width,
+            tile_height,
+        );
+        if let Some(name) = sprite_tile_name(&image, Some(frame), raw_master) {
+            tile_names.insert(first_gid + id, name);
+        }
+    }
+    Ok(())
+}
+
+/// Name of the tile raw whose sprite is the `frame` of the image at `path`, or the whole image
+/// without a frame. The images are relative to the assets
+fn sprite_tile_name(path: &Path, frame: Option<(u32, u32, u32, u32)>, raw_master: &RawMaster) -> Option<String> {
+    raw_master
+        .raws
+        .tiles
+        .iter()
+        .find(|tile| {
+            raw_master
+                .raws
+                .sprite_image(&tile.sprite)
+                .is_some_and(|(image, sprite_frame)| sprite_frame == frame && path.ends_with(image))
+        })
+        .map(|tile| tile.name.clone())
+}
+
+/// Value of the property `name` of the properties of `node`
+fn property<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .filter(|node| node.has_tag_name("properties"))
+        .flat_map(|properties| properties.children())
+        .find(|property| property.has_tag_name("property") && property.attribute("name") == Some(name))
+        .and_then(|property| property.attribute("value"))
+}
+
+/// Escapes the characters of `text` that cannot be in an XML attribute
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn attribute<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, String> {
+    node.attribute(name)
+        .ok_or_else(|| format!("<{}> has no {} attribute", node.tag_name().name(), name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TileType, read_raws};
+
+    fn tile_raws() -> RawMaster {
+        let mut raw_master = RawMaster {
+            raws: read_raws(),
+            ..Default::default()
+        };
+        raw_master.load();
+        raw_master
+    }
+
+    #[test]
+    fn iso_map_is_loaded() {
+        let chunks = load_tmx("./assets/iso_map.tmx", &tile_raws()).unwrap().chunks;
+        let mut count = 0;
+        for (&coords, chunk) in &chunks {
+            for tile in chunk.tiles() {
+                assert_eq!(tile.pos.chunk(), coords);
+                count += 1;
+            }
+        }
+        // The full ground, a wall and a hill
+        assert_eq!(count, 144 + 11 + 33);
+
+        let name = |pos: Position| chunks[&pos.chunk()].tile_names[&pos].as_str();
+        assert_eq!(name(Position::new(0, 0, 0)), "GrassBlock");
+        assert_eq!(name(Position::new(-4, -4, 0)), "SandBlock");
+        assert_eq!(name(Position::new(-3, -3, 1)), "StoneBlock");
+    }
+
+    #[test]
+    fn spritesheet_tiles_are_matched_by_frame() {
+        let mut raw_master = tile_raws();
+        let sheet = r#"[SpriteBundle(name: "sheet", image: "sheet.png", frames: [
+            SpriteFrame(name: "sheet_sand", rect: (32, 32, 32, 32)),
+        ])]"#;
+        raw_master.raws.sprites.extend(ron::from_str::<Vec<_>>(sheet).unwrap());
+        raw_master.raws.tiles[raw_master.tile_index["SandBlock"]].sprite = "sheet_sand".to_string();
+
+        let tmx = r#"<map orientation="isometric" width="2" height="1" tilewidth="32" tileheight="16" infinite="0">
+ <tileset firstgid="1" name="sheet" tilewidth="32" tileheight="32" tilecount="4" columns="2">
+  <image source="sheet.png" width="64" height="64"/>
+  <tile id="0"><properties><property name="name" value="GrassBlock"/></properties></tile>
+ </tileset>
+ <layer id="1" name="0" width="2" height="1"><data encoding="csv">1,4</data></layer>
+</map>"#;
+        let path = std::env::temp_dir().join("my_game_spritesheet_map.tmx");
+        fs::write(&path, tmx).unwrap();
+        let chunks = load_tmx(&path, &raw_master).unwrap().chunks;
+        let name = |pos: Position| chunks[&pos.chunk()].tile_names[&pos].as_str();
+        assert_eq!(name(Position::new(0, 0, 0)), "GrassBlock");
+        assert_eq!(name(Position::new(0, -1, 0)), "SandBlock");
+    }
+
+    #[test]
+    fn saved_map_loads_back() {
+        let raw_master = tile_raws();
+        let tiles = vec![
+            ("GrassBlock".to_string(), Position::new(3, -2, 0)),
+            ("StoneBlock".to_string(), Position::new(5, 1, 0)),
+            ("GrassFloor".to_string(), Position::new(3, -2, 1)),
+        ];
+        let objects = vec![
+            ("Dummy".to_string(), Position::new(3, -2, 2)),
+            ("Tree".to_string(), Position::new(5, 1, 1)),
+        ];
+        let path = std::env::temp_dir().join("my_game_saved_map.tmx");
+        save_tmx(&path, &tiles, &objects, &raw_master).unwrap();
+        let map = load_tmx(&path, &raw_master).unwrap();
+
+        let mut loaded: Vec<(String, Position)> = map
+            .chunks
+            .values()
+            .flat_map(|chunk| chunk.tile_names.iter().map(|(pos, name)| (name.clone(), *pos)))
+            .collect();
+        loaded.sort_by_key(|(_, pos)| (pos.z, pos.x, pos.y));
+        assert_eq!(loaded, tiles);
+        assert_eq!(map.objects, objects);
+        assert_eq!(
+            map.chunks[&Position::new(3, -2, 1).chunk()].get_tile(&Position::new(3, -2, 1)),
+            Some(TileType::Floor)
+        );
+    }
+}
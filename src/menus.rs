@@ -3,7 +3,7 @@ mod main_menu;
 mod settings_menu;
 mod video_menu;
 
-use crate::{despawn_screen, GameState, MenuState};
+use crate::{despawn_screen, GameState, MenuState, WorldCreationState};
 use bevy::prelude::*;
 
 use audio_menu::*;
@@ -12,7 +12,7 @@ use settings_menu::*;
 use video_menu::*;
 
 // This plugin manages the menu, with 5 different screens:
-// - a main menu with "New Game", "Settings", "Quit"
+// - a main menu with "New Game", "Load Game", "Settings", "Quit"
 // - a settings menu with two submenus and a back button
 // - two settings screen with a setting that can be set and a back button
 pub struct MenuPlugin;
@@ -29,6 +29,7 @@ impl Plugin for MenuPlugin {
             // Systems to handle the main menu screen
             .add_systems(OnEnter(MenuState::Main), main_menu_setup)
             .add_systems(OnExit(MenuState::Main), despawn_screen::<OnMainMenuScreen>)
+            .add_systems(Update, load_game_failed_text.run_if(in_state(MenuState::Main)))
             // Systems to handle the settings menu screen
             .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
             .add_systems(OnExit(MenuState::Settings), despawn_screen::<OnSettingsMenuScreen>)
@@ -53,7 +54,11 @@ impl Plugin for MenuPlugin {
                 despawn_screen::<OnSoundSettingsMenuScreen>,
             )
             // Common systems to all screens that handles buttons behavior
-            .add_systems(Update, (menu_action, button_system).run_if(in_state(GameState::InMenu)));
+            .add_systems(Update, menu_action.run_if(in_state(GameState::InMenu)))
+            .add_systems(
+                Update,
+                button_system.run_if(in_state(GameState::InMenu).or(in_state(WorldCreationState::MapSettings))),
+            );
     }
 }
 
@@ -72,6 +77,7 @@ pub struct SelectedOption;
 #[derive(Component)]
 pub enum MenuButtonAction {
     Play,
+    LoadGame,
     Settings,
     SettingsDisplay,
     SettingsSound,
@@ -5,12 +5,17 @@
 use bevy::{app::AppExit, prelude::*};
 
 use super::*;
+use crate::save::LoadGameFailed;
 use bevy::color::palettes::css::CRIMSON;
 
 // Tag component used to tag entities added on the main menu screen
 #[derive(Component)]
 pub struct OnMainMenuScreen;
 
+// Tag component of the text telling why the game saved could not be loaded
+#[derive(Component)]
+pub struct LoadGameFailedText;
+
 // This system handles changing all buttons color based on mouse interaction
 pub fn button_system(
     mut interaction_query: Query<
@@ -112,8 +117,9 @@ pub fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         },
                     ));
 
-                    // Display three buttons for each action available from the main menu:
+                    // Display four buttons for each action available from the main menu:
                     // - new game
+                    // - load game
                     // - settings
                     // - quit
                     parent
@@ -128,6 +134,18 @@ pub fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             parent.spawn((ImageNode::new(icon), button_icon_node.clone()));
                             parent.spawn((Text::new("New Game"), button_text_font.clone(), TextColor(TEXT_COLOR)));
                         });
+                    parent
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            MenuButtonAction::LoadGame,
+                        ))
+                        .with_children(|parent| {
+                            let icon = asset_server.load("icons/load.png");
+                            parent.spawn((ImageNode::new(icon), button_icon_node.clone()));
+                            parent.spawn((Text::new("Load Game"), button_text_font.clone(), TextColor(TEXT_COLOR)));
+                        });
                     parent
                         .spawn((
                             Button,
@@ -152,15 +170,40 @@ pub fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             parent.spawn((ImageNode::new(icon), button_icon_node));
                             parent.spawn((Text::new("Quit"), button_text_font, TextColor(TEXT_COLOR)));
                         });
+                    // Empty until loading the game fails
+                    parent.spawn((
+                        Text::default(),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..default()
+                        },
+                        LoadGameFailedText,
+                    ));
                 });
         });
 }
 
+// This system shows on the main menu why the game saved could not be loaded
+pub fn load_game_failed_text(
+    mut load_failed: MessageReader<LoadGameFailed>,
+    mut text: Single<&mut Text, With<LoadGameFailedText>>,
+) {
+    if let Some(LoadGameFailed(error)) = load_failed.read().last() {
+        text.0.clone_from(error);
+    }
+}
+
 pub fn menu_action(
     interaction_query: Query<(&Interaction, &MenuButtonAction), (Changed<Interaction>, With<Button>)>,
     mut app_exit_events: MessageWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut map_creation_state: ResMut<NextState<WorldCreationState>>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -172,6 +215,8 @@ pub fn menu_action(
                     menu_state.set(MenuState::Disabled);
                     game_state.set(GameState::InMapCreation);
                 }
+                // The menu stays until the game is loaded, it is still there if it can not be
+                MenuButtonAction::LoadGame => map_creation_state.set(WorldCreationState::LoadGame),
                 MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
                 MenuButtonAction::SettingsDisplay => {
                     menu_state.set(MenuState::SettingsDisplay);
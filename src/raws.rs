@@ -1,5 +1,6 @@
-use crate::GameState;
-use bevy::prelude::{App, OnEnter, Plugin, ResMut};
+use bevy::asset::LoadState;
+use bevy::image::TextureAtlasLayout;
+use bevy::prelude::{App, AssetServer, Assets, Image, IntoScheduleConfigs, Plugin, Res, ResMut, Startup, Update};
 use std::fs;
 
 mod tile_bundle;
@@ -8,32 +9,73 @@ mod creature_bundle;
 use creature_bundle::*;
 mod item_bundle;
 use item_bundle::*;
+mod biome_bundle;
+use biome_bundle::*;
+mod sprite_bundle;
+use sprite_bundle::*;
 
 mod rawmaster;
 pub use rawmaster::*;
+mod sprite_registry;
+pub use sprite_registry::*;
 
 const TILES_FILE: &str = "./data/tiles/tiles.ron";
 const CREATURES_FILE: &str = "./data/creatures/creatures.ron";
 const ITEMS_FILE: &str = "./data/items/items.ron";
+const BIOMES_FILE: &str = "./data/biomes/biomes.ron";
+const SPRITES_FILE: &str = "./data/sprites/sprites.ron";
 
 pub struct RawsPlugin;
 
 impl Plugin for RawsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<RawMaster>()
-            .add_systems(OnEnter(GameState::InMapCreation), load_creatures_from_ron);
+            .init_resource::<SpriteRegistry>()
+            // Loaded once for every game, a saved game is loaded without going through the map creation
+            .add_systems(Startup, load_creatures_from_ron)
+            .add_systems(
+                Update,
+                pack_sprites.run_if(|sprite_registry: Res<SpriteRegistry>| !sprite_registry.is_packed()),
+            );
     }
 }
 
-fn load_creatures_from_ron(mut raw_master: ResMut<RawMaster>) {
-    let ron_tiles = fs::read_to_string(TILES_FILE).expect("Unable to read the raws file");
-    raw_master.raws.tiles = ron::from_str(&ron_tiles).expect("Failed to deserialize from RON");
+fn load_creatures_from_ron(
+    mut raw_master: ResMut<RawMaster>,
+    mut sprite_registry: ResMut<SpriteRegistry>,
+    asset_server: Res<AssetServer>,
+) {
+    raw_master.raws = read_raws();
+    raw_master.load();
+    sprite_registry.load(&raw_master.raws, |path| asset_server.load(path.to_string()));
+}
 
+/// Reads the raws from their RON files in the data folder
+pub fn read_raws() -> Raws {
+    let ron_tiles = fs::read_to_string(TILES_FILE).expect("Unable to read the raws file");
     let ron_creatures = fs::read_to_string(CREATURES_FILE).expect("Unable to read the raws file");
-    raw_master.raws.creatures = ron::from_str(&ron_creatures).expect("Failed to deserialize from RON");
-
     let ron_items = fs::read_to_string(ITEMS_FILE).expect("Unable to read the raws file");
-    raw_master.raws.items = ron::from_str(&ron_items).expect("Failed to deserialize from RON");
+    let ron_biomes = fs::read_to_string(BIOMES_FILE).expect("Unable to read the raws file");
+    let ron_sprites = fs::read_to_string(SPRITES_FILE).expect("Unable to read the raws file");
+    Raws {
+        tiles: ron::from_str(&ron_tiles).expect("Failed to deserialize from RON"),
+        creatures: ron::from_str(&ron_creatures).expect("Failed to deserialize from RON"),
+        items: ron::from_str(&ron_items).expect("Failed to deserialize from RON"),
+        biomes: ron::from_str(&ron_biomes).expect("Failed to deserialize from RON"),
+        sprites: ron::from_str(&ron_sprites).expect("Failed to deserialize from RON"),
+    }
+}
 
-    raw_master.load();
+fn pack_sprites(
+    mut sprite_registry: ResMut<SpriteRegistry>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    asset_server: Res<AssetServer>,
+) {
+    sprite_registry.pack(&mut images, &mut layouts, |image| {
+        matches!(
+            asset_server.load_state(image),
+            LoadState::NotLoaded | LoadState::Loading
+        )
+    });
 }
@@ -0,0 +1,58 @@
+use crate::{Biome, TileType};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BiomeBundle {
+    pub biome: Biome,
+    /// Layers under the surface covered by the tiles of the elevation bands, deeper is underground
+    pub soil_depth: i32,
+    pub underground: BiomeTiles,
+    /// Tiles by elevation, the first band whose `up_to` is over the column elevation is used
+    pub elevations: Vec<BiomeTiles>,
+    /// Vegetation and items scattered over the floor of the biome, the first one placed on a
+    /// column is the only one there
+    #[serde(default)]
+    pub placements: Vec<Placement>,
+}
+
+/// Material of the tiles used for a part of a biome
+#[derive(Deserialize, Debug, Clone)]
+pub struct BiomeTiles {
+    /// Highest elevation of the band, no value means no limit
+    #[serde(default)]
+    pub up_to: Option<i32>,
+    /// Start of the names of the tile raws, like `Grass` for `GrassBlock` and `GrassRampTopLeft`
+    pub material: String,
+}
+
+/// Tile or item scattered over the floor of a biome
+#[derive(Deserialize, Debug, Clone)]
+pub struct Placement {
+    /// Name of the tile or item raw
+    pub name: String,
+    /// Chance of each floor column to get one, from 0.0 to 1.0
+    pub density: f64,
+    /// Minimum number of tiles between it and anything else placed in the chunk
+    #[serde(default)]
+    pub spacing: i32,
+}
+
+impl BiomeBundle {
+    /// Name of the tile of type `tile_type` at the layer `z` of a column with the given `elevation`,
+    /// the water tiles are the same in every biome
+    #[must_use]
+    pub fn tile_name(&self, elevation: i32, z: i32, tile_type: TileType) -> String {
+        if tile_type.is_water() {
+            return tile_type.raw_suffix().to_string();
+        }
+        let tiles = if z <= elevation - self.soil_depth {
+            &self.underground
+        } else {
+            self.elevations
+                .iter()
+                .find(|band| band.up_to.is_none_or(|up_to| elevation <= up_to))
+                .unwrap_or(&self.underground)
+        };
+        format!("{}{}", tiles.material, tile_type.raw_suffix())
+    }
+}
@@ -7,4 +7,12 @@ pub struct ItemBundle {
     pub sprite: String,
     pub heal: Option<u32>,
     pub damage: Option<u32>,
+    pub light: Option<LightBundle>,
+}
+
+/// Light given by an item, its color goes from 0.0 to 1.0
+#[derive(Deserialize, Debug, Clone)]
+pub struct LightBundle {
+    pub range: u32,
+    pub color: (f32, f32, f32),
 }
@@ -1,13 +1,12 @@
-use super::{CreatureBundle, ItemBundle, TileBundle};
-use crate::{
-    Backpack, Creature, CurrentMap, CursorHighlight, Direction, DoDamage, Equipment, GameState, Health, Item,
-    PathfindingSteps, Position, ProvidesHeal, SpawnEntity, Tile, Viewshed, ViewshedHighlight, on_click,
+use super::{
+    BiomeBundle, CreatureBundle, ItemBundle, Placement, SpriteBundle, SpriteRegistry, TileBundle, TileHighlight,
 };
-use bevy::picking::Pickable;
-use bevy::prelude::{
-    AssetServer, Commands, Component, DespawnOnExit, Entity, MessageWriter, Name, On, Over, Pointer, Query, Res,
-    ResMut, Resource, Sprite, Transform, warn,
+use crate::{
+    Backpack, Biome, Column, Creature, CurrentMap, CursorHighlight, Direction, DoDamage, Equipment, EquippedBy,
+    GameState, Health, InBackpack, Item, LightSource, PathfindingSteps, Position, ProvidesHeal, Renderable, Tile,
+    TileData, Viewshed, ViewshedHighlight,
 };
+use bevy::prelude::{Commands, DespawnOnExit, Entity, LinearRgba, Name, ResMut, Resource, Transform, warn};
 use std::collections::{HashMap, HashSet};
 use std::ops::Neg;
 
@@ -23,6 +22,22 @@ pub struct Raws {
     pub tiles: Vec<TileBundle>,
     pub creatures: Vec<CreatureBundle>,
     pub items: Vec<ItemBundle>,
+    pub biomes: Vec<BiomeBundle>,
+    pub sprites: Vec<SpriteBundle>,
+}
+
+impl Raws {
+    /// Path of the image of the sprite `name` and its part of the image for the frames of a sheet
+    #[must_use]
+    pub fn sprite_image(&self, name: &str) -> Option<(&str, Option<(u32, u32, u32, u32)>)> {
+        self.sprites.iter().find_map(|bundle| {
+            if bundle.frames.is_empty() && bundle.name == name {
+                return Some((bundle.image.as_str(), None));
+            }
+            let frame = bundle.frames.iter().find(|frame| frame.name == name)?;
+            Some((bundle.image.as_str(), Some(frame.rect)))
+        })
+    }
 }
 
 #[derive(Default, Resource, Debug)]
@@ -31,6 +46,7 @@ pub struct RawMaster {
     pub tile_index: HashMap<String, usize>,
     pub creature_index: HashMap<String, usize>,
     pub item_index: HashMap<String, usize>,
+    pub biome_index: HashMap<Biome, usize>,
 }
 
 impl RawMaster {
@@ -58,12 +74,32 @@ impl RawMaster {
             |item| &item.name,
             "Item",
         );
+
+        self.biome_index.clear();
+        for (i, biome) in self.raws.biomes.iter().enumerate() {
+            if self.biome_index.insert(biome.biome, i).is_some() {
+                warn!("Biome: {:?} is duplicated in the data files", biome.biome);
+            }
+        }
+    }
+
+    /// Name of the tile raw used to spawn `tile` in a column of the given terrain
+    pub fn biome_tile_name(&self, column: &Column, tile: &TileData) -> String {
+        let biome = &self.raws.biomes[self.biome_index[&column.biome]];
+        biome.tile_name(column.elevation, tile.pos.z, tile.tile_type)
+    }
+
+    /// Vegetation and items scattered over the floor of the given biome
+    pub fn biome_placements(&self, biome: Biome) -> &[Placement] {
+        self.biome_index
+            .get(&biome)
+            .map_or(&[], |index| &self.raws.biomes[*index].placements)
     }
 
     pub fn spawn_named_tile(
         &self,
         commands: &mut Commands,
-        asset_server: &Res<AssetServer>,
+        sprites: &SpriteRegistry,
         current_map: &mut ResMut<CurrentMap>,
         key: String,
         pos: SpawnType,
@@ -77,16 +113,30 @@ impl RawMaster {
         commands.entity(entity).insert(Tile {});
         // Name
         commands.entity(entity).insert(Name::new(tile_template.name.clone()));
-        // Sprite
-        commands
-            .entity(entity)
-            .insert(Sprite::from_image(asset_server.load(tile_template.sprite.clone())));
         // Position
-        commands.entity(entity).insert(spawn_position(pos));
+        spawn_position(commands, entity, pos);
+        // Texture
+        let texture = sprites.id(tile_template.sprite_for(current_map.layout.rotation));
+        if let Some(id) = texture {
+            commands.entity(entity).insert(Renderable(id));
+        }
+        // The highlights are drawn over the map but are not part of it
+        let Some(highlight) = tile_template.highlight else {
+            // The way and the sight through them come from the data of their chunk, not only the
+            // tiles spawned
+            if let SpawnType::AtPosition { x, y, z } = pos {
+                current_map.tiles.insert(Position { x, y, z }, entity);
+            }
+            // The map tiles are drawn by the mesh of their chunk layer
+            return entity;
+        };
+        // Sprite
+        if let Some(id) = texture {
+            commands.entity(entity).insert(sprites.sprite(id));
+        }
         // Transform
         if let SpawnType::AtPosition { x, y, z } = pos {
             let coord = current_map.layout.tile_to_world_pos(Position { x, y, z });
-            current_map.tiles.insert(Position { x, y, z }, entity);
             //commands.entity(entity).with_children(|b| {
             //    b.spawn((
             //        Text2d(format!("{},{}", x, y)),
@@ -95,68 +145,30 @@ impl RawMaster {
             //        Transform::from_xyz(0.0, 8.0, 10.0),
             //    ));
             //});
-            if tile_template.blocker {
-                current_map.blocked_coords.insert(Position { x, y, z });
-            }
-            if tile_template.name == "SelectedBlock" {
+            if highlight == TileHighlight::Cursor {
                 commands.entity(entity).insert(Transform::from_xyz(
                     coord.x,
                     coord.y,
                     coord.y.neg() / 100.0 + coord.z + 0.002,
                 ));
-            } else if tile_template.name == "ViewshedFloor" {
-                commands.entity(entity).insert(Transform::from_xyz(
-                    coord.x,
-                    coord.y,
-                    ((coord.y.neg() as f32) / 100.0) + coord.z + 0.001,
-                ));
             } else {
                 commands.entity(entity).insert(Transform::from_xyz(
                     coord.x,
                     coord.y,
-                    ((coord.y.neg() as f32) / 100.0) + coord.z,
+                    ((coord.y.neg() as f32) / 100.0) + coord.z + 0.001,
                 ));
             }
         }
-        if tile_template.name == "SelectedBlock" {
-            commands.entity(entity).insert(CursorHighlight {});
-        } else {
-            if tile_template.name == "ViewshedFloor" {
+        match highlight {
+            TileHighlight::Cursor => {
+                commands.entity(entity).insert(CursorHighlight {});
+            }
+            TileHighlight::Viewshed => {
                 commands.entity(entity).insert(ViewshedHighlight {});
+                // Pathfinding
+                commands.entity(entity).insert(PathfindingSteps::new());
             }
-            // Pathfinding
-            commands.entity(entity).insert(PathfindingSteps::new());
-            // Hovering Observers
-            commands.entity(entity).observe(
-                |ev: On<Pointer<Over>>,
-                 mut commands: Commands,
-                 mut spawn_event: MessageWriter<SpawnEntity>,
-                 pos_query: Query<&Position>,
-                 highlighted_query: Query<(Entity, &Position, &CursorHighlight)>| {
-                    // TODO this is bad we need something better
-                    let mut new_selected = HashSet::new();
-                    if let Ok(pos) = pos_query.get(ev.entity) {
-                        new_selected.insert(pos);
-                        spawn_event.write(SpawnEntity {
-                            name: "SelectedBlock".to_string(),
-                            pos: SpawnType::AtPosition {
-                                x: pos.x,
-                                y: pos.y,
-                                z: pos.z,
-                            },
-                        });
-                    }
-                    for (entity, pos, _) in &highlighted_query {
-                        if !new_selected.contains(pos) {
-                            commands.entity(entity).despawn();
-                        }
-                    }
-                },
-            );
         }
-        // Picking Observers
-        commands.entity(entity).insert(Pickable::default());
-        commands.entity(entity).observe(on_click);
 
         entity
     }
@@ -164,7 +176,7 @@ impl RawMaster {
     pub fn spawn_named_creature(
         &self,
         commands: &mut Commands,
-        asset_server: &Res<AssetServer>,
+        sprites: &SpriteRegistry,
         current_map: &mut ResMut<CurrentMap>,
         key: String,
         pos: SpawnType,
@@ -181,11 +193,11 @@ impl RawMaster {
             .entity(entity)
             .insert(Name::new(creature_template.name.clone()));
         // Sprite
-        commands
-            .entity(entity)
-            .insert(Sprite::from_image(asset_server.load(creature_template.sprite.clone())));
+        if let Some(id) = sprites.id(&creature_template.sprite) {
+            commands.entity(entity).insert((Renderable(id), sprites.sprite(id)));
+        }
         // Position
-        commands.entity(entity).insert(spawn_position(pos));
+        spawn_position(commands, entity, pos);
 
         // Place in the world
         if let SpawnType::AtPosition { x, y, z } = pos {
@@ -203,6 +215,8 @@ impl RawMaster {
         };
         // Race
         commands.entity(entity).insert(creature_template.race);
+        // Faction
+        commands.entity(entity).insert(creature_template.race.get_faction());
         // Attributes
         commands.entity(entity).insert(creature_template.race.get_attributes());
         // Health
@@ -222,7 +236,7 @@ impl RawMaster {
     pub fn spawn_named_item(
         &self,
         commands: &mut Commands,
-        asset_server: &Res<AssetServer>,
+        sprites: &SpriteRegistry,
         current_map: &mut ResMut<CurrentMap>,
         key: String,
         pos: SpawnType,
@@ -237,11 +251,11 @@ impl RawMaster {
         // Name
         commands.entity(entity).insert(Name::new(item_template.name.clone()));
         // Sprite
-        commands
-            .entity(entity)
-            .insert(Sprite::from_image(asset_server.load(item_template.sprite.clone())));
+        if let Some(id) = sprites.id(&item_template.sprite) {
+            commands.entity(entity).insert((Renderable(id), sprites.sprite(id)));
+        }
         // Position
-        commands.entity(entity).insert(spawn_position(pos));
+        spawn_position(commands, entity, pos);
         // Transform
         if let SpawnType::AtPosition { x, y, z } = pos {
             let coord = current_map.layout.tile_to_world_pos(Position { x, y, z });
@@ -258,41 +272,44 @@ impl RawMaster {
         if let Some(dmg) = item_template.damage {
             commands.entity(entity).insert(DoDamage(dmg));
         }
+        if let Some(light) = &item_template.light {
+            let (red, green, blue) = light.color;
+            commands.entity(entity).insert(LightSource {
+                range: light.range,
+                color: LinearRgba::rgb(red, green, blue),
+            });
+        }
         entity
     }
 
     pub fn spawn_named_entity(
         &self,
         commands: &mut Commands,
-        asset_server: &Res<AssetServer>,
+        sprites: &SpriteRegistry,
         current_map: &mut ResMut<CurrentMap>,
         key: String,
         pos: SpawnType,
     ) -> Option<Entity> {
         if self.tile_index.contains_key(&key) {
-            return Some(self.spawn_named_tile(commands, asset_server, current_map, key, pos));
+            return Some(self.spawn_named_tile(commands, sprites, current_map, key, pos));
         }
         if self.creature_index.contains_key(&key) {
-            return Some(self.spawn_named_creature(commands, asset_server, current_map, key, pos));
+            return Some(self.spawn_named_creature(commands, sprites, current_map, key, pos));
         }
         if self.item_index.contains_key(&key) {
-            return Some(self.spawn_named_item(commands, asset_server, current_map, key, pos));
+            return Some(self.spawn_named_item(commands, sprites, current_map, key, pos));
         }
         None
     }
 }
 
-fn spawn_position(pos: SpawnType) -> impl Component {
-    // Spawn in the specified location
+/// Puts the new `entity` in the specified location, on the map or with the creature carrying it
+fn spawn_position(commands: &mut Commands, entity: Entity, pos: SpawnType) {
     match pos {
-        SpawnType::AtPosition { x, y, z } => Position { x, y, z },
-        //SpawnType::Carried { by } => InBackpack { owner: by },
-        _ => Position { x: 0, y: 0, z: 0 },
-        //    SpawnType::Equipped { by } => {
-        //        let slot = find_slot_for_equippable_item(tag, raws);
-        //        EquippedBy { owner: by, slot }
-        //    }
-    }
+        SpawnType::AtPosition { x, y, z } => commands.entity(entity).insert(Position { x, y, z }),
+        SpawnType::Carried { by } => commands.entity(entity).insert(InBackpack { owner: by }),
+        SpawnType::Equipped { by } => commands.entity(entity).insert(EquippedBy { owner: by }),
+    };
 }
 
 fn process_raws<T, F>(
@@ -0,0 +1,22 @@
+use serde::Deserialize;
+
+/// Image of the assets folder and the sprites cut from it
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpriteBundle {
+    /// Identifier of the sprite when the whole image is one
+    pub name: String,
+    /// Path of the image, relative to the assets folder
+    pub image: String,
+    /// Named parts of a sprite sheet, each one is a sprite and the whole image is not
+    #[serde(default)]
+    pub frames: Vec<SpriteFrame>,
+}
+
+/// Part of the image of a sprite sheet
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpriteFrame {
+    /// Identifier of the sprite
+    pub name: String,
+    /// Pixels of the image covered by the sprite, as `(x, y, width, height)` from the top left corner
+    pub rect: (u32, u32, u32, u32),
+}
@@ -0,0 +1,316 @@
+use super::Raws;
+use crate::TextureId;
+use bevy::asset::AssetId;
+use bevy::image::{TextureAtlas, TextureAtlasBuilder, TextureAtlasLayout};
+use bevy::prelude::{Assets, Handle, Image, Rect, Resource, Sprite, URect, UVec2, Vec2, default, warn};
+use std::collections::{HashMap, HashSet};
+
+/// Atlas with the sprites of the tile raws, drawn by the meshes of the chunk layers
+pub const TILES_ATLAS: &str = "tiles";
+
+/// Sprites of the raws by identifier, packed into an atlas for each kind of raw once their images
+/// are loaded.
+///
+/// The identifiers follow the order of the sprite raws and of the frames of each sprite sheet, so
+/// the same raws always give the same [`TextureId`]s.
+#[derive(Resource, Default, Debug)]
+pub struct SpriteRegistry {
+    ids: HashMap<String, TextureId>,
+    sprites: Vec<RegisteredSprite>,
+    /// Sprites going to each atlas, only the ones used by the raws are packed
+    groups: Vec<(String, Vec<TextureId>)>,
+    atlases: HashMap<String, SpriteAtlas>,
+    packed: bool,
+}
+
+#[derive(Debug)]
+struct RegisteredSprite {
+    image: Handle<Image>,
+    /// Part of the image of a sprite sheet
+    rect: Option<URect>,
+    /// Atlas and index of the sprite in its layout, once packed
+    packed: Option<(String, usize)>,
+}
+
+/// Image where sprites are packed and the place of each one
+#[derive(Debug)]
+struct SpriteAtlas {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    /// Copy of the layout, to find the sprites without going through the assets
+    rects: TextureAtlasLayout,
+}
+
+impl SpriteRegistry {
+    /// Registers the sprites of `raws`, their images are loaded with `load_image` from their path
+    pub fn load(&mut self, raws: &Raws, mut load_image: impl FnMut(&str) -> Handle<Image>) {
+        *self = Self::default();
+        for bundle in &raws.sprites {
+            let image = load_image(&bundle.image);
+            if bundle.frames.is_empty() {
+                self.register(&bundle.name, image.clone(), None);
+            }
+            for frame in &bundle.frames {
+                let (x, y, width, height) = frame.rect;
+                let rect = URect::new(x, y, x + width, y + height);
+                self.register(&frame.name, image.clone(), Some(rect));
+            }
+        }
+
+        // A sprite used by several kinds of raws is only packed in the first atlas
+        let mut grouped = HashSet::new();
+        let tiles = raws
+            .tiles
+            .iter()
+            .flat_map(|tile| tile.sprites().map(move |sprite| (&tile.name, sprite)));
+        let creatures = raws.creatures.iter().map(|creature| (&creature.name, &creature.sprite));
+        let items = raws.items.iter().map(|item| (&item.name, &item.sprite));
+        self.groups = vec![
+            (TILES_ATLAS.to_string(), self.used_by(tiles, &mut grouped)),
+            ("creatures".to_string(), self.used_by(creatures, &mut grouped)),
+            ("items".to_string(), self.used_by(items, &mut grouped)),
+        ];
+    }
+
+    fn register(&mut self, name: &str, image: Handle<Image>, rect: Option<URect>) {
+        if self.ids.contains_key(name) {
+            warn!("Sprite: {} is duplicated in the data files", name);
+            return;
+        }
+        self.ids.insert(name.to_string(), TextureId(self.sprites.len() as u32));
+        self.sprites.push(RegisteredSprite {
+            image,
+            rect,
+            packed: None,
+        });
+    }
+
+    /// Sprites of the `raws`, given by their name and the identifier of their sprite, that are not
+    /// in `grouped` yet
+    fn used_by<'a>(
+        &self,
+        raws: impl Iterator<Item = (&'a String, &'a String)>,
+        grouped: &mut HashSet<TextureId>,
+    ) -> Vec<TextureId> {
+        let mut used = Vec::new();
+        for (name, sprite) in raws {
+            match self.id(sprite) {
+                Some(id) if grouped.insert(id) => used.push(id),
+                Some(_) => {}
+                None => warn!("Sprite: {} of {} is not in the sprite raws", sprite, name),
+            }
+        }
+        used
+    }
+
+    /// Identifier of the sprite `name`
+    #[must_use]
+    pub fn id(&self, name: &str) -> Option<TextureId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Sprite drawing `id`, from its atlas once they are packed
+    #[must_use]
+    pub fn sprite(&self, id: TextureId) -> Sprite {
+        let Some(registered) = self.sprites.get(id.0 as usize) else {
+            return Sprite::default();
+        };
+        match &registered.packed {
+            Some((atlas, index)) => {
+                let atlas = &self.atlases[atlas];
+                Sprite::from_atlas_image(
+                    atlas.image.clone(),
+                    TextureAtlas {
+                        layout: atlas.layout.clone(),
+                        index: *index,
+                    },
+                )
+            }
+            None => Sprite {
+                image: registered.image.clone(),
+                rect: registered.rect.map(|rect| rect.as_rect()),
+                ..default()
+            },
+        }
+    }
+
+    /// Image of the atlas `name`, once packed
+    #[must_use]
+    pub fn atlas_image(&self, name: &str) -> Option<&Handle<Image>> {
+        self.atlases.get(name).map(|atlas| &atlas.image)
+    }
+
+    /// Size in pixels of the sprite `id` and its corners in its atlas, from 0.0 to 1.0, once packed
+    #[must_use]
+    pub fn atlas_rect(&self, id: TextureId) -> Option<(Vec2, Rect)> {
+        let (atlas, index) = self.sprites.get(id.0 as usize)?.packed.as_ref()?;
+        let layout = &self.atlases[atlas].rects;
+        let rect = layout.textures[*index].as_rect();
+        let size = layout.size.as_vec2();
+        Some((rect.size(), Rect::from_corners(rect.min / size, rect.max / size)))
+    }
+
+    /// Tells if the sprites are packed in their atlases
+    #[must_use]
+    pub fn is_packed(&self) -> bool {
+        self.packed
+    }
+
+    /// Packs the sprites into their atlases once none of their images is `loading`, the ones that
+    /// failed to load are left out. Tells if the sprites are packed
+    pub fn pack(
+        &mut self,
+        images: &mut Assets<Image>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+        loading: impl Fn(&Handle<Image>) -> bool,
+    ) -> bool {
+        let grouped = self.groups.iter().flat_map(|(_, ids)| ids);
+        if self.packed || grouped.clone().any(|id| loading(&self.sprites[id.0 as usize].image)) {
+            return self.packed;
+        }
+
+        for (name, ids) in &self.groups {
+            // The sheets are packed whole, their frames are found inside them afterwards
+            let built = {
+                let mut builder = TextureAtlasBuilder::default();
+                // The padding keeps the neighbour sprites from bleeding into the borders of each one
+                builder.padding(UVec2::ONE);
+                let mut added: HashSet<AssetId<Image>> = HashSet::new();
+                for id in ids {
+                    let handle = &self.sprites[id.0 as usize].image;
+                    if let Some(image) = images.get(handle)
+                        && added.insert(handle.id())
+                    {
+                        builder.add_texture(Some(handle.id()), image);
+                    }
+                }
+                if added.is_empty() {
+                    continue;
+                }
+                builder.build()
+            };
+            let (mut layout, sources, image) = match built {
+                Ok(built) => built,
+                Err(err) => {
+                    warn!("Unable to pack the sprites of the {} atlas: {}", name, err);
+                    continue;
+                }
+            };
+
+            for id in ids {
+                let sprite = &mut self.sprites[id.0 as usize];
+                let Some(index) = sources.texture_index(&sprite.image) else { continue };
+                let index = match sprite.rect {
+                    Some(rect) => {
+                        let origin = layout.textures[index].min;
+                        layout.add_texture(URect::from_corners(origin + rect.min, origin + rect.max))
+                    }
+                    None => index,
+                };
+                sprite.packed = Some((name.clone(), index));
+            }
+            self.atlases.insert(
+                name.clone(),
+                SpriteAtlas {
+                    image: images.add(image),
+                    layout: layouts.add(layout.clone()),
+                    rects: layout,
+                },
+            );
+        }
+        self.packed = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raws::{SpriteBundle, SpriteFrame, TileBundle};
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    fn image(width: u32, height: u32) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[255, 255, 255, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    fn tile(name: &str, sprite: &str) -> TileBundle {
+        TileBundle {
+            name: name.to_string(),
+            sprite: sprite.to_string(),
+            rotated_sprites: Vec::new(),
+            blocker: false,
+            tile_type: None,
+            highlight: None,
+        }
+    }
+
+    #[test]
+    fn sheets_are_cut_into_frames_with_stable_ids() {
+        let mut images = Assets::<Image>::default();
+        let mut layouts = Assets::<TextureAtlasLayout>::default();
+        let handles = HashMap::from([
+            ("block.png", images.add(image(32, 32))),
+            ("ramps.png", images.add(image(64, 32))),
+        ]);
+        let mut raws = Raws::default();
+        raws.sprites = vec![
+            SpriteBundle {
+                name: "block".to_string(),
+                image: "block.png".to_string(),
+                frames: Vec::new(),
+            },
+            SpriteBundle {
+                name: "ramps".to_string(),
+                image: "ramps.png".to_string(),
+                frames: vec![
+                    SpriteFrame {
+                        name: "ramp_left".to_string(),
+                        rect: (0, 0, 32, 32),
+                    },
+                    SpriteFrame {
+                        name: "ramp_right".to_string(),
+                        rect: (32, 0, 32, 32),
+                    },
+                ],
+            },
+        ];
+        raws.tiles = vec![
+            tile("Block", "block"),
+            tile("RampLeft", "ramp_left"),
+            tile("RampRight", "ramp_right"),
+        ];
+
+        let mut registry = SpriteRegistry::default();
+        registry.load(&raws, |path| handles[path].clone());
+        assert_eq!(registry.id("block"), Some(TextureId(0)));
+        assert_eq!(registry.id("ramp_left"), Some(TextureId(1)));
+        assert_eq!(registry.id("ramp_right"), Some(TextureId(2)));
+        // The sheet itself is not a sprite
+        assert_eq!(registry.id("ramps"), None);
+        assert_eq!(
+            registry.sprite(TextureId(2)).rect,
+            Some(Rect::new(32.0, 0.0, 64.0, 32.0))
+        );
+
+        assert!(!registry.pack(&mut images, &mut layouts, |_| true));
+        assert!(registry.pack(&mut images, &mut layouts, |_| false));
+        assert!(registry.atlas_image(TILES_ATLAS).is_some());
+        let (size, left) = registry.atlas_rect(TextureId(1)).unwrap();
+        let (_, right) = registry.atlas_rect(TextureId(2)).unwrap();
+        assert_eq!(size, Vec2::new(32.0, 32.0));
+        // The frames are side by side in the atlas like in their sheet
+        assert_eq!(left.max.x, right.min.x);
+        assert_eq!(left.min.y, right.min.y);
+    }
+}
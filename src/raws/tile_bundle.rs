@@ -1,3 +1,4 @@
+use crate::{TileType, ViewRotation};
 use serde::Deserialize;
 
 // TODO maybe in the future we can use bundles and optionals
@@ -5,5 +6,46 @@ use serde::Deserialize;
 pub struct TileBundle {
     pub name: String,
     pub sprite: String,
+    /// Sprites drawn when the view is turned 90°, 180° and 270°, for the tiles that do not look
+    /// the same from every side
+    #[serde(default)]
+    pub rotated_sprites: Vec<String>,
     pub blocker: bool,
+    /// Shape of the tile, without it the tile is a block or a floor depending on `blocker`
+    #[serde(default)]
+    pub tile_type: Option<TileType>,
+    /// Highlight drawn over the map by the tile, which is then not part of the map
+    #[serde(default)]
+    pub highlight: Option<TileHighlight>,
+}
+
+/// What a tile drawn over the map highlights
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileHighlight {
+    /// The tile under the cursor
+    Cursor,
+    /// A tile seen by a creature
+    Viewshed,
+}
+
+impl TileBundle {
+    #[must_use]
+    pub fn tile_type(&self) -> TileType {
+        self.tile_type
+            .unwrap_or(if self.blocker { TileType::Block } else { TileType::Floor })
+    }
+
+    /// Sprite drawing the tile with the view turned by `rotation`
+    #[must_use]
+    pub fn sprite_for(&self, rotation: ViewRotation) -> &String {
+        match rotation.quarter_turns() {
+            0 => &self.sprite,
+            turns => self.rotated_sprites.get(turns - 1).unwrap_or(&self.sprite),
+        }
+    }
+
+    /// Every sprite the tile can be drawn with
+    pub fn sprites(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.sprite).chain(&self.rotated_sprites)
+    }
 }
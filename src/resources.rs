@@ -1,9 +1,19 @@
 use bevy::prelude::*;
 
+mod entity_registry;
+pub use entity_registry::*;
+mod fog_of_war;
+pub use fog_of_war::*;
+mod lighting;
+pub use lighting::*;
 mod map;
 pub use map::*;
+mod spatial_index;
+pub use spatial_index::*;
 mod states;
 pub use states::*;
+mod world_gen_params;
+pub use world_gen_params::*;
 mod world_map;
 pub use world_map::*;
 
@@ -12,6 +22,13 @@ pub struct ResourcesPlugin;
 impl Plugin for ResourcesPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<CurrentMap>()
+            .init_resource::<EntityRegistry>()
+            .init_resource::<FogOfWar>()
+            .add_message::<FogChanged>()
+            .init_resource::<GameClock>()
+            .init_resource::<LightMap>()
+            .add_message::<LightChanged>()
+            .init_resource::<WorldChunks>()
             .init_resource::<WorldMap>()
             // configure our fixed timestep schedule to run twenty times per second
             .insert_resource(Time::<Fixed>::from_seconds(0.05))
@@ -0,0 +1,204 @@
+use crate::{Backpack, Chasing, EntityId, Equipment, EquippedBy, InBackpack};
+use bevy::ecs::lifecycle::HookContext;
+use bevy::ecs::world::DeferredWorld;
+use bevy::prelude::{Entity, Resource};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+/// Entity of each [`EntityId`] given to the creatures and items of the game
+#[derive(Resource, Debug, Default)]
+pub struct EntityRegistry {
+    entities: HashMap<EntityId, Entity>,
+    ids: HashMap<Entity, EntityId>,
+    /// The ids are never given twice in a game, even once their entity is despawned
+    next: u64,
+}
+
+impl EntityRegistry {
+    /// Registry giving ids from `next` on, for a game whose ids up to it were given already
+    #[must_use]
+    pub fn starting_at(next: EntityId) -> Self {
+        Self {
+            next: next.0,
+            ..Self::default()
+        }
+    }
+
+    /// Id the next registered entity gets
+    #[must_use]
+    pub fn next_id(&self) -> EntityId {
+        EntityId(self.next)
+    }
+
+    /// Gives a new id to `entity`, it still has to be inserted into it
+    pub fn register(&mut self, entity: Entity) -> EntityId {
+        let id = EntityId(self.next);
+        self.insert(id, entity);
+        id
+    }
+
+    /// Gives `entity` the `id` it had before, like in a saved game
+    pub fn insert(&mut self, id: EntityId, entity: Entity) {
+        self.next = self.next.max(id.0 + 1);
+        self.entities.insert(id, entity);
+        self.ids.insert(entity, id);
+    }
+
+    /// Takes `id` out of the registry if it still belongs to `entity`
+    pub fn remove(&mut self, id: EntityId, entity: Entity) {
+        if self.entities.get(&id) == Some(&entity) {
+            self.entities.remove(&id);
+            self.ids.remove(&entity);
+        }
+    }
+
+    #[must_use]
+    pub fn entity(&self, id: EntityId) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    #[must_use]
+    pub fn id(&self, entity: Entity) -> Option<EntityId> {
+        self.ids.get(&entity).copied()
+    }
+}
+
+/// Hook of [`EntityId`], its entity is forgotten once despawned or given another id
+pub fn forget_entity_id(mut world: DeferredWorld, context: HookContext) {
+    let Some(id) = world.get::<EntityId>(context.entity).copied() else { return };
+    if let Some(mut registry) = world.get_resource_mut::<EntityRegistry>() {
+        registry.remove(id, context.entity);
+    }
+}
+
+/// Component pointing at other entities. It is saved with their [`EntityId`]s, which are mapped back
+/// to the entities spawned for them when it is loaded
+pub trait EntityLinks: Sized {
+    /// The component with ids in place of entities
+    type Saved: Serialize + DeserializeOwned;
+
+    /// Gives `None` when the entity pointed at has no id
+    fn to_ids(&self, registry: &EntityRegistry) -> Option<Self::Saved>;
+
+    /// Gives `None` when no entity has the id pointed at
+    fn from_ids(saved: &Self::Saved, registry: &EntityRegistry) -> Option<Self>;
+}
+
+impl EntityLinks for Chasing {
+    type Saved = EntityId;
+
+    fn to_ids(&self, registry: &EntityRegistry) -> Option<EntityId> {
+        registry.id(self.0)
+    }
+
+    fn from_ids(saved: &EntityId, registry: &EntityRegistry) -> Option<Self> {
+        Some(Self(registry.entity(*saved)?))
+    }
+}
+
+impl EntityLinks for InBackpack {
+    type Saved = EntityId;
+
+    fn to_ids(&self, registry: &EntityRegistry) -> Option<EntityId> {
+        registry.id(self.owner)
+    }
+
+    fn from_ids(saved: &EntityId, registry: &EntityRegistry) -> Option<Self> {
+        Some(Self {
+            owner: registry.entity(*saved)?,
+        })
+    }
+}
+
+impl EntityLinks for EquippedBy {
+    type Saved = EntityId;
+
+    fn to_ids(&self, registry: &EntityRegistry) -> Option<EntityId> {
+        registry.id(self.owner)
+    }
+
+    fn from_ids(saved: &EntityId, registry: &EntityRegistry) -> Option<Self> {
+        Some(Self {
+            owner: registry.entity(*saved)?,
+        })
+    }
+}
+
+/// The items without an id are left out
+impl EntityLinks for Backpack {
+    type Saved = Vec<EntityId>;
+
+    fn to_ids(&self, registry: &EntityRegistry) -> Option<Vec<EntityId>> {
+        Some(self.content.iter().filter_map(|entity| registry.id(*entity)).collect())
+    }
+
+    fn from_ids(saved: &Vec<EntityId>, registry: &EntityRegistry) -> Option<Self> {
+        Some(Self {
+            content: saved.iter().filter_map(|id| registry.entity(*id)).collect(),
+        })
+    }
+}
+
+/// A held item without an id leaves the hand empty
+impl EntityLinks for Equipment {
+    type Saved = Option<EntityId>;
+
+    fn to_ids(&self, registry: &EntityRegistry) -> Option<Option<EntityId>> {
+        Some(self.holding_right_hand.and_then(|entity| registry.id(entity)))
+    }
+
+    fn from_ids(saved: &Option<EntityId>, registry: &EntityRegistry) -> Option<Self> {
+        Some(Self {
+            holding_right_hand: saved.and_then(|id| registry.entity(id)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::World;
+    use std::collections::HashSet;
+
+    #[test]
+    fn links_are_saved_with_ids_and_remapped() {
+        let mut world = World::new();
+        world.init_resource::<EntityRegistry>();
+        let creature = world.spawn_empty().id();
+        let item = world.spawn_empty().id();
+        let mut registry = world.resource_mut::<EntityRegistry>();
+        let creature_id = registry.register(creature);
+        let item_id = registry.register(item);
+        assert_eq!((creature_id, item_id), (EntityId(0), EntityId(1)));
+        world.entity_mut(creature).insert(creature_id);
+        world.entity_mut(item).insert(item_id);
+
+        let registry = world.resource::<EntityRegistry>();
+        let backpack = Backpack {
+            content: HashSet::from([item]),
+        };
+        let saved = backpack.to_ids(registry).unwrap();
+        assert_eq!(saved, vec![item_id]);
+        assert_eq!(InBackpack { owner: creature }.to_ids(registry), Some(creature_id));
+
+        // Loaded back into other entities, the ids stay the same
+        let mut loaded = EntityRegistry::starting_at(registry.next_id());
+        let (new_creature, new_item) = (Entity::from_raw_u32(20).unwrap(), Entity::from_raw_u32(21).unwrap());
+        loaded.insert(creature_id, new_creature);
+        loaded.insert(item_id, new_item);
+        assert_eq!(
+            Backpack::from_ids(&saved, &loaded).unwrap().content,
+            HashSet::from([new_item])
+        );
+        assert_eq!(Chasing::from_ids(&creature_id, &loaded), Some(Chasing(new_creature)));
+        assert_eq!(loaded.register(Entity::from_raw_u32(22).unwrap()), EntityId(2));
+
+        // A despawned entity is forgotten, its id is not given again
+        world.despawn(item);
+        let registry = world.resource::<EntityRegistry>();
+        assert_eq!(registry.entity(item_id), None);
+        assert_eq!(registry.id(creature), Some(creature_id));
+        assert_eq!(registry.next_id(), EntityId(2));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{Faction, Position};
+use bevy::prelude::{Message, Resource};
+use std::collections::{HashMap, HashSet};
+
+/// What the creatures of a faction know of the map
+#[derive(Default, Debug, Clone)]
+pub struct Vision {
+    /// Tiles seen at some point, they are remembered once out of sight
+    pub revealed: HashSet<Position>,
+    /// Tiles seen right now by some creature of the faction
+    pub visible: HashSet<Position>,
+}
+
+/// How much the faction shown knows of a tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileFog {
+    /// Never seen, it is not drawn
+    Unexplored,
+    /// Seen before but out of sight now, it is drawn dimmed
+    Remembered,
+    Visible,
+}
+
+/// Vision of each faction, only the one of the faction of the player is drawn
+#[derive(Debug, Resource)]
+pub struct FogOfWar {
+    pub visions: HashMap<Faction, Vision>,
+    /// Faction whose vision is drawn
+    pub shown: Faction,
+}
+
+impl Default for FogOfWar {
+    fn default() -> Self {
+        Self {
+            visions: HashMap::new(),
+            shown: Faction::Colony,
+        }
+    }
+}
+
+/// Sent with the tiles whose [`TileFog`] changed for the faction shown
+#[derive(Message, Debug)]
+pub struct FogChanged(pub Vec<Position>);
+
+impl FogOfWar {
+    /// Replaces what `faction` sees right now by `visible`, which is revealed too. Gives the tiles
+    /// that came into or went out of its sight
+    pub fn update(&mut self, faction: Faction, visible: HashSet<Position>) -> Vec<Position> {
+        let vision = self.visions.entry(faction).or_default();
+        let changed = vision.visible.symmetric_difference(&visible).copied().collect();
+        vision.revealed.extend(&visible);
+        vision.visible = visible;
+        changed
+    }
+
+    /// How much the faction shown knows of the tile at `pos`
+    #[must_use]
+    pub fn fog(&self, pos: &Position) -> TileFog {
+        match self.visions.get(&self.shown) {
+            Some(vision) if vision.visible.contains(pos) => TileFog::Visible,
+            Some(vision) if vision.revealed.contains(pos) => TileFog::Remembered,
+            _ => TileFog::Unexplored,
+        }
+    }
+
+    /// Tells if the faction shown sees the tile at `pos` right now
+    #[must_use]
+    pub fn is_visible(&self, pos: &Position) -> bool {
+        self.fog(pos) == TileFog::Visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_out_of_sight_are_remembered() {
+        let mut fog = FogOfWar::default();
+        let (first, second) = (Position::new(0, 0, 3), Position::new(1, 0, 3));
+        assert_eq!(fog.fog(&first), TileFog::Unexplored);
+
+        assert_eq!(fog.update(Faction::Colony, HashSet::from([first])), vec![first]);
+        assert_eq!(fog.fog(&first), TileFog::Visible);
+
+        let mut changed = fog.update(Faction::Colony, HashSet::from([second]));
+        changed.sort_by_key(|pos| pos.x);
+        assert_eq!(changed, vec![first, second]);
+        assert_eq!(fog.fog(&first), TileFog::Remembered);
+        assert!(fog.is_visible(&second));
+
+        // What other factions see is not shown
+        fog.update(Faction::Hostile, HashSet::from([Position::new(5, 5, 3)]));
+        assert_eq!(fog.fog(&Position::new(5, 5, 3)), TileFog::Unexplored);
+    }
+}
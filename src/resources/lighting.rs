@@ -0,0 +1,183 @@
+use crate::Position;
+use bevy::prelude::{Entity, LinearRgba, Message, Resource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+pub const HOURS_PER_DAY: f32 = 24.0;
+
+/// Light of the night sky, nothing is darker than this
+const MOONLIGHT: f32 = 0.125;
+
+/// The light of the sky only changes by these steps, so the tiles are not tinted again every frame
+const AMBIENT_STEPS: f32 = 16.0;
+
+/// In-game time, the sun rises at six and sets at eighteen
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Resource)]
+pub struct GameClock {
+    /// Hours since the midnight of the first day
+    pub hours: f32,
+    /// In-game hours passing by each second the game runs
+    pub speed: f32,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self {
+            // The colony arrives in the morning
+            hours: 8.0,
+            speed: 0.1,
+        }
+    }
+}
+
+impl GameClock {
+    pub fn advance(&mut self, seconds: f32) {
+        self.hours += seconds * self.speed;
+    }
+
+    /// Hour of the current day, from 0.0 to 24.0
+    #[must_use]
+    pub fn hour_of_day(&self) -> f32 {
+        self.hours.rem_euclid(HOURS_PER_DAY)
+    }
+
+    /// Light of the sun from 0.0 at night to 1.0 at day, the dawn and the dusk last about two hours
+    #[must_use]
+    pub fn sun_level(&self) -> f32 {
+        // The sun is highest at noon and lowest at midnight
+        let height = -(self.hour_of_day() / HOURS_PER_DAY * TAU).cos();
+        (0.5 + height * 2.0).clamp(0.0, 1.0)
+    }
+}
+
+/// Light reaching each tile, from the sky and from the [`LightSource`](crate::LightSource)s
+#[derive(Debug, Default, Resource)]
+pub struct LightMap {
+    /// Light of the sky over every tile, from [`MOONLIGHT`] to 1.0
+    pub ambient: f32,
+    /// Light given by the light sources to the tiles they reach
+    pub lights: HashMap<Position, LinearRgba>,
+    /// Light given by each light source, they are added up into `lights`
+    pub sources: HashMap<Entity, HashMap<Position, LinearRgba>>,
+}
+
+/// Sent when the light of some tiles changed, to tint them again
+#[derive(Message, Debug)]
+pub enum LightChanged {
+    /// The light of the sky changed, which lights every tile
+    Ambient,
+    Tiles(Vec<Position>),
+}
+
+impl LightMap {
+    /// Sets the light of the sky from the `sun_level`. Tells if it changed
+    pub fn set_ambient(&mut self, sun_level: f32) -> bool {
+        let ambient = MOONLIGHT + (1.0 - MOONLIGHT) * sun_level;
+        let ambient = (ambient * AMBIENT_STEPS).round() / AMBIENT_STEPS;
+        if self.ambient == ambient {
+            return false;
+        }
+        self.ambient = ambient;
+        true
+    }
+
+    /// Replaces the light given by the light sources by `lights`. Gives the tiles whose light changed
+    pub fn update_lights(&mut self, lights: HashMap<Position, LinearRgba>) -> Vec<Position> {
+        let mut changed: Vec<Position> = self
+            .lights
+            .iter()
+            .filter(|(pos, color)| lights.get(pos) != Some(color))
+            .map(|(pos, _)| *pos)
+            .collect();
+        changed.extend(lights.keys().filter(|pos| !self.lights.contains_key(pos)));
+        self.lights = lights;
+        changed
+    }
+
+    /// Adds up the light of the `sources` into `lights`. Gives the tiles whose light changed
+    pub fn add_up_sources(&mut self) -> Vec<Position> {
+        let mut lights: HashMap<Position, LinearRgba> = HashMap::new();
+        for (lit, color) in self.sources.values().flatten() {
+            *lights.entry(*lit).or_insert(LinearRgba::BLACK) += *color;
+        }
+        self.update_lights(lights)
+    }
+
+    /// Light reaching the tile at `pos`, each channel goes up to 1.0
+    #[must_use]
+    pub fn light(&self, pos: &Position) -> LinearRgba {
+        let ambient = LinearRgba::rgb(self.ambient, self.ambient, self.ambient);
+        let light = match self.lights.get(pos) {
+            Some(color) => ambient + *color,
+            None => ambient,
+        };
+        LinearRgba::rgb(light.red.min(1.0), light.green.min(1.0), light.blue.min(1.0))
+    }
+
+    /// How lit the tile at `pos` is, from 0.0 to 1.0
+    #[must_use]
+    pub fn brightness(&self, pos: &Position) -> f32 {
+        let light = self.light(pos);
+        light.red.max(light.green).max(light.blue)
+    }
+}
+
+/// Strength of the light of a source with `range` at `distance` tiles of it, it fades out linearly
+#[must_use]
+pub fn light_falloff(distance: u32, range: u32) -> f32 {
+    (1.0 - distance as f32 / (range + 1) as f32).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_sky_darkens_at_night() {
+        let mut clock = GameClock {
+            hours: 12.0,
+            speed: 1.0,
+        };
+        assert_eq!(clock.sun_level(), 1.0);
+        clock.advance(12.0);
+        assert_eq!(clock.sun_level(), 0.0);
+        assert_eq!(clock.hour_of_day(), 0.0);
+        // Half lit when the sun rises
+        clock.advance(6.0);
+        assert!((clock.sun_level() - 0.5).abs() < 0.01);
+
+        let mut light_map = LightMap::default();
+        assert!(light_map.set_ambient(0.0));
+        assert!(!light_map.set_ambient(0.001));
+        assert_eq!(light_map.brightness(&Position::ZERO), 0.125);
+    }
+
+    #[test]
+    fn light_sources_add_to_the_sky() {
+        let mut light_map = LightMap::default();
+        light_map.set_ambient(0.0);
+        let (lit, unlit) = (Position::new(0, 0, 3), Position::new(5, 0, 3));
+        let torch = LinearRgba::rgb(1.0, 0.5, 0.0);
+        assert_eq!(light_map.update_lights(HashMap::from([(lit, torch)])), vec![lit]);
+        assert_eq!(light_map.light(&lit), LinearRgba::rgb(1.0, 0.625, 0.125));
+        assert_eq!(light_map.brightness(&unlit), 0.125);
+
+        assert_eq!(light_map.update_lights(HashMap::from([(lit, torch)])), vec![]);
+        assert_eq!(light_map.update_lights(HashMap::new()), vec![lit]);
+
+        // Two sources lighting the same tile add up
+        let (first, second) = (Entity::from_raw_u32(1).unwrap(), Entity::from_raw_u32(2).unwrap());
+        light_map.sources.insert(first, HashMap::from([(lit, torch)]));
+        light_map
+            .sources
+            .insert(second, HashMap::from([(lit, torch), (unlit, torch)]));
+        assert_eq!(light_map.add_up_sources().len(), 2);
+        assert_eq!(light_map.lights[&lit].green, 1.0);
+        light_map.sources.remove(&second);
+        assert_eq!(light_map.add_up_sources().len(), 2);
+        assert_eq!(light_map.lights[&lit].green, 0.5);
+        assert_eq!(light_falloff(0, 4), 1.0);
+        assert_eq!(light_falloff(5, 4), 0.0);
+    }
+}
@@ -1,15 +1,96 @@
-use crate::Position;
-use crate::map::Layout;
+use super::SpatialIndex;
+use crate::map::{Chunk, Layout};
+use crate::{Position, RawMaster, TileType, tile_opaque};
 use bevy::prelude::{Entity, Resource};
 use std::collections::{HashMap, HashSet};
 
 // TODO convert this to resources in map creation CurrentWorld
 #[derive(Default, Debug, Resource)]
 pub struct CurrentMap {
-    // This can probably be changed to a HashSet<Entity> as each entity has a Position component
-    pub tiles: HashMap<Position, Entity>,
-    pub entities: HashMap<Position, Entity>,
-    pub items: HashMap<Position, Entity>,
+    /// Spawned tiles, the decorations share the position of the tile under them
+    pub tiles: SpatialIndex<Entity>,
+    pub entities: SpatialIndex<Entity>,
+    pub items: SpatialIndex<Entity>,
     pub layout: Layout,
+    /// Tiles of the loaded chunks blocking the way, spawned or culled
     pub blocked_coords: HashSet<Position>,
+    /// Type of the tiles of the loaded chunks, spawned or culled
+    pub tile_types: HashMap<Position, TileType>,
+}
+
+impl CurrentMap {
+    /// Adds every tile of a loaded `chunk` and its `decorations` to the tiles the creatures walk and
+    /// see through, whether they are spawned or not
+    pub fn add_chunk(&mut self, chunk: &Chunk, decorations: &[(String, Position)], raw_master: &RawMaster) {
+        for tile in chunk.tiles() {
+            self.set_tile(tile.pos, Some((tile.tile_type, tile.tile_type == TileType::Block)));
+        }
+        // Decorations over a tile keep its type
+        for (name, pos) in decorations {
+            let Some(index) = raw_master.tile_index.get(name) else { continue };
+            let decoration = &raw_master.raws.tiles[*index];
+            self.tile_types.entry(*pos).or_insert(decoration.tile_type());
+            if decoration.blocker {
+                self.blocked_coords.insert(*pos);
+            }
+        }
+    }
+
+    /// Takes the tiles of a `chunk` unloaded and its `decorations` out of the map
+    pub fn remove_chunk(&mut self, chunk: &Chunk, decorations: &[(String, Position)]) {
+        let positions = chunk.tiles().map(|tile| tile.pos);
+        for pos in positions.chain(decorations.iter().map(|(_, pos)| *pos)) {
+            self.set_tile(pos, None);
+        }
+    }
+
+    /// Puts the tile of `tile_type` at `pos`, which tells if it blocks the way, or nothing
+    pub fn set_tile(&mut self, pos: Position, tile: Option<(TileType, bool)>) {
+        self.tile_types.remove(&pos);
+        self.blocked_coords.remove(&pos);
+        if let Some((tile_type, blocker)) = tile {
+            self.tile_types.insert(pos, tile_type);
+            if blocker {
+                self.blocked_coords.insert(pos);
+            }
+        }
+    }
+
+    /// Tells if a creature can move between `from` and `to` on different layers, which is only
+    /// possible through a ramp or stair rising from the lower one towards the upper one
+    #[must_use]
+    pub fn climbs(&self, from: Position, to: Position) -> bool {
+        let (lower, upper) = if from.z < to.z { (from, to) } else { (to, from) };
+        self.tile_types
+            .get(&lower)
+            .and_then(|tile_type| tile_type.climbs_to())
+            .is_some_and(|orientation| lower + orientation.step() + Position::new(0, 0, 1) == upper)
+    }
+
+    /// Tells if nothing can be seen through `pos`, neither sight nor light go past blocking or opaque
+    /// tiles, or the ones that are not spawned
+    #[must_use]
+    pub fn blocks_sight(&self, pos: Position) -> bool {
+        self.blocked_coords.contains(&pos)
+            || self.tile_types.get(&pos).is_none_or(|tile_type| tile_opaque(*tile_type))
+    }
+}
+
+/// Data of every generated [`Chunk`] of the world, spawned or not
+#[derive(Default, Debug, Resource)]
+pub struct WorldChunks {
+    /// Chunks of the world by chunk coordinates
+    pub chunks: HashMap<(i32, i32), Chunk>,
+    /// Named tiles placed on top of the terrain of each chunk, like trees
+    pub decorations: HashMap<(i32, i32), Vec<(String, Position)>>,
+    /// Chunk coordinates whose tiles are currently spawned
+    pub loaded: HashSet<(i32, i32)>,
+    /// Set for hand-authored maps, the chunks out of them are empty instead of generated
+    pub bounded: bool,
+    /// Chunks whose tiles changed since they were generated, the others are generated again when
+    /// a saved game is loaded
+    pub modified: HashSet<(i32, i32)>,
+    /// Chunks whose placements were scattered already, they are not placed again when the chunk is
+    /// generated anew
+    pub scattered: HashSet<(i32, i32)>,
 }
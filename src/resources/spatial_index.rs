@@ -0,0 +1,200 @@
+use crate::Position;
+use std::collections::HashMap;
+
+/// Values placed at positions of the world, like the entities standing on each tile.
+///
+/// The values are grouped by the chunk of their position, so the ones of a chunk or around a
+/// position are found without going through all of them. A position can hold many values.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<T> {
+    chunks: HashMap<(i32, i32), HashMap<Position, Vec<T>>>,
+}
+
+impl<T> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        Self { chunks: HashMap::new() }
+    }
+}
+
+impl<T: Copy + PartialEq> SpatialIndex<T> {
+    /// Adds `value` at `pos`, after the values already there
+    pub fn insert(&mut self, pos: Position, value: T) {
+        self.chunks
+            .entry(pos.chunk())
+            .or_default()
+            .entry(pos)
+            .or_default()
+            .push(value);
+    }
+
+    /// Removes `value` from `pos`, tells if it was there
+    pub fn remove(&mut self, pos: &Position, value: T) -> bool {
+        let chunk = pos.chunk();
+        let Some(positions) = self.chunks.get_mut(&chunk) else {
+            return false;
+        };
+        let Some(values) = positions.get_mut(pos) else {
+            return false;
+        };
+        let Some(index) = values.iter().position(|v| *v == value) else {
+            return false;
+        };
+        values.remove(index);
+        if values.is_empty() {
+            positions.remove(pos);
+            if positions.is_empty() {
+                self.chunks.remove(&chunk);
+            }
+        }
+        true
+    }
+
+    /// Removes and gives back every value at `pos`
+    pub fn remove_at(&mut self, pos: &Position) -> Vec<T> {
+        let chunk = pos.chunk();
+        let Some(positions) = self.chunks.get_mut(&chunk) else {
+            return Vec::new();
+        };
+        let values = positions.remove(pos).unwrap_or_default();
+        if positions.is_empty() {
+            self.chunks.remove(&chunk);
+        }
+        values
+    }
+
+    /// Moves `value` from `from` to `to`, tells if it was at `from`
+    pub fn relocate(&mut self, from: &Position, to: Position, value: T) -> bool {
+        let found = self.remove(from, value);
+        if found {
+            self.insert(to, value);
+        }
+        found
+    }
+
+    /// Values at `pos`, in the order they were added
+    #[must_use]
+    pub fn get(&self, pos: &Position) -> &[T] {
+        self.chunks
+            .get(&pos.chunk())
+            .and_then(|positions| positions.get(pos))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Tells if there is any value at `pos`
+    #[must_use]
+    pub fn contains(&self, pos: &Position) -> bool {
+        !self.get(pos).is_empty()
+    }
+
+    /// Iterates over every value and its position
+    pub fn iter(&self) -> impl Iterator<Item = (Position, T)> + '_ {
+        self.chunks.keys().flat_map(|chunk| self.in_chunk(*chunk))
+    }
+
+    /// Iterates over the values in the chunk at the `chunk` coordinates
+    pub fn in_chunk(&self, chunk: (i32, i32)) -> impl Iterator<Item = (Position, T)> + '_ {
+        self.chunks
+            .get(&chunk)
+            .into_iter()
+            .flatten()
+            .flat_map(|(pos, values)| values.iter().map(|value| (*pos, *value)))
+    }
+
+    /// Removes and gives back the values in the chunk at the `chunk` coordinates
+    pub fn remove_chunk(&mut self, chunk: (i32, i32)) -> Vec<(Position, T)> {
+        self.chunks
+            .remove(&chunk)
+            .into_iter()
+            .flatten()
+            .flat_map(|(pos, values)| values.into_iter().map(move |value| (pos, value)))
+            .collect()
+    }
+
+    /// Iterates over the values whose coordinates are all between the ones of `min` and `max`,
+    /// both included
+    pub fn in_box(&self, min: Position, max: Position) -> impl Iterator<Item = (Position, T)> + '_ {
+        // The chunk coordinates only depend on `x - y` and `x + y`
+        let (min_chunk_x, _) = Position::new(min.x - max.y, 0, 0).chunk();
+        let (max_chunk_x, _) = Position::new(max.x - min.y, 0, 0).chunk();
+        let (_, min_chunk_y) = Position::new(min.x + min.y, 0, 0).chunk();
+        let (_, max_chunk_y) = Position::new(max.x + max.y, 0, 0).chunk();
+        self.chunks
+            .iter()
+            .filter(move |((x, y), _)| {
+                (min_chunk_x..=max_chunk_x).contains(x) && (min_chunk_y..=max_chunk_y).contains(y)
+            })
+            .flat_map(|(_, positions)| positions.iter())
+            .filter(move |(pos, _)| {
+                (min.x..=max.x).contains(&pos.x) && (min.y..=max.y).contains(&pos.y) && (min.z..=max.z).contains(&pos.z)
+            })
+            .flat_map(|(pos, values)| values.iter().map(|value| (*pos, *value)))
+    }
+
+    /// Iterates over the values at most `radius` steps away from `center`
+    pub fn in_range(&self, center: Position, radius: u32) -> impl Iterator<Item = (Position, T)> + '_ {
+        let radius = radius as i32;
+        let offset = Position::new(radius, radius, radius);
+        self.in_box(center - offset, center + offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_hold_many_values() {
+        let mut index = SpatialIndex::default();
+        let pos = Position::new(3, -7, 20);
+        index.insert(pos, 1);
+        index.insert(pos, 2);
+        assert_eq!(index.get(&pos), &[1, 2]);
+
+        assert!(index.relocate(&pos, Position::new(4, -7, 20), 1));
+        assert!(!index.remove(&pos, 1));
+        assert_eq!(index.get(&pos), &[2]);
+        assert!(index.remove(&pos, 2));
+        assert!(!index.contains(&pos));
+        assert_eq!(index.get(&Position::new(4, -7, 20)), &[1]);
+
+        index.insert(pos, 3);
+        index.insert(pos, 4);
+        assert_eq!(index.remove_at(&pos), vec![3, 4]);
+        assert!(!index.contains(&pos));
+    }
+
+    #[test]
+    fn box_and_chunk_queries_find_the_same_values_than_a_full_scan() {
+        let mut index = SpatialIndex::default();
+        for x in -40..40 {
+            for y in -40..40 {
+                index.insert(Position::new(x, y, (x * y).rem_euclid(3)), (x, y));
+            }
+        }
+
+        let (min, max) = (Position::new(-21, 5, 0), Position::new(13, 37, 1));
+        let mut found: Vec<(Position, (i32, i32))> = index.in_box(min, max).collect();
+        let mut expected: Vec<(Position, (i32, i32))> = index
+            .iter()
+            .filter(|(pos, _)| (min.x..=max.x).contains(&pos.x) && (min.y..=max.y).contains(&pos.y) && pos.z <= max.z)
+            .collect();
+        found.sort_by_key(|(_, value)| *value);
+        expected.sort_by_key(|(_, value)| *value);
+        assert_eq!(found, expected);
+
+        let center = Position::new(2, 2, 1);
+        assert_eq!(
+            index.in_range(center, 3).count(),
+            index
+                .iter()
+                .filter(|(pos, _)| pos.unsigned_distance_to(center) <= 3)
+                .count()
+        );
+
+        let chunk = Position::new(0, 0, 0).chunk();
+        let in_chunk: Vec<(Position, (i32, i32))> = index.in_chunk(chunk).collect();
+        assert!(!in_chunk.is_empty() && in_chunk.iter().all(|(pos, _)| pos.chunk() == chunk));
+        assert_eq!(index.remove_chunk(chunk).len(), in_chunk.len());
+        assert_eq!(index.in_chunk(chunk).count(), 0);
+    }
+}
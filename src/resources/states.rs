@@ -52,6 +52,9 @@ pub enum MenuState {
 pub enum WorldCreationState {
     MapSettings,
     MapGeneration,
+    MapImport,
+    /// Loading the saved game, from the main menu
+    LoadGame,
     #[default]
     Disabled,
 }
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::Path};
+
+/// Knobs of the world generation used by [`WorldMap`](crate::WorldMap), they can be shared as
+/// presets in RON files. The default values are the ones of the noise-rs complex planet example.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct WorldGenParams {
+    /// Frequency of the planet's continents. Higher frequency produces
+    /// smaller, more numerous continents. This value is measured in radians.
+    pub continent_frequency: f64,
+
+    /// Lacunarity of the planet's continents. Changing this value produces
+    /// slightly different continents. For the best results, this value should
+    /// be random, but close to 2.0.
+    pub continent_lacunarity: f64,
+
+    /// Lacunarity of the planet's mountains. Changing the value produces
+    /// slightly different mountains. For the best results, this value should
+    /// be random, but close to 2.0.
+    pub mountain_lacunarity: f64,
+
+    /// Lacunarity of the planet's hills. Changing this value produces
+    /// slightly different hills. For the best results, this value should be
+    /// random, but close to 2.0.
+    pub hills_lacunarity: f64,
+
+    /// Lacunarity of the planet's plains. Changing this value produces
+    /// slightly different plains. For the best results, this value should be
+    /// random, but close to 2.0.
+    pub plains_lacunarity: f64,
+
+    /// Lacunarity of the planet's badlands. Changing this value produces
+    /// slightly different badlands. For the best results, this value should
+    /// be random, but close to 2.0.
+    pub badlands_lacunarity: f64,
+
+    /// Specifies the "twistiness" of the mountains.
+    pub mountains_twist: f64,
+
+    /// Specifies the "twistiness" of the hills.
+    pub hills_twist: f64,
+
+    /// Specifies the "twistiness" of the badlands.
+    pub badlands_twist: f64,
+
+    /// Specifies the planet's sea level. This value must be between -1.0
+    /// (minimum planet elevation) and +1.0 (maximum planet elevation).
+    pub sea_level: f64,
+
+    /// Specifies the level on the planet in which continental shelves appear.
+    /// This value must be between -1.0 (minimum planet elevation) and +1.0
+    /// (maximum planet elevation), and must be less than `sea_level`.
+    pub shelf_level: f64,
+
+    /// Determines the amount of mountainous terrain that appears on the
+    /// planet. Values range from 0.0 (no mountains) to 1.0 (all terrain is
+    /// covered in mountains). Mountains terrain will overlap hilly terrain.
+    /// Because the badlands terrain may overlap parts of the mountainous
+    /// terrain, setting `mountains_amount` to 1.0 may not completely cover the
+    /// terrain in mountains.
+    pub mountains_amount: f64,
+
+    /// Determines the amount of hilly terrain that appears on the planet.
+    /// Values range from 0.0 (no hills) to 1.0 (all terrain is covered in
+    /// hills). This value must be less than `mountains_amount`. Because the
+    /// mountains terrain will overlap parts of the hilly terrain, and the
+    /// badlands terrain may overlap parts of the hilly terrain, setting
+    /// `hills_amount` to 1.0 may not completely cover the terrain in hills.
+    pub hills_amount: f64,
+
+    /// Determines the amount of badlands terrain that covers the planet.
+    /// Values range from 0.0 (no badlands) to 1.0 (all terrain is covered in
+    /// badlands). Badlands terrain will overlap any other type of terrain.
+    pub badlands_amount: f64,
+
+    /// Offset to apply to the terrain type definition. Low values (< 1.0)
+    /// cause the rough areas to appear only at high elevations. High values
+    /// (> 2.0) cause the rough areas to appear at any elevation. The
+    /// percentage of rough areas on the planet are independent of this value.
+    pub terrain_offset: f64,
+
+    /// Specifies the amount of "glaciation" on the mountains. This value
+    /// should be close to 1.0 and greater than 1.0.
+    pub mountain_glaciation: f64,
+
+    /// Maximum depth of the rivers, in planetary elevation units.
+    pub river_depth: f64,
+
+    /// Elevation over the sea level under which the land is a beach, in planetary
+    /// elevation units.
+    pub beach_height: f64,
+
+    /// Frequency of the caves, measured in tiles. The lower the wider the caves are.
+    pub caves_frequency: f64,
+}
+
+impl WorldGenParams {
+    /// Loads the parameters from the RON file at `path`, missing fields take the default values
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let ron_params = fs::read_to_string(path)?;
+        Ok(ron::from_str(&ron_params)?)
+    }
+
+    /// Saves the parameters in the RON file at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let ron_params = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron_params)?;
+        Ok(())
+    }
+}
+
+impl Default for WorldGenParams {
+    fn default() -> Self {
+        Self {
+            continent_frequency: 1.0,
+            continent_lacunarity: 2.208984375,
+            mountain_lacunarity: 2.142578125,
+            hills_lacunarity: 2.162109375,
+            plains_lacunarity: 2.314453125,
+            badlands_lacunarity: 2.212890625,
+            mountains_twist: 1.0,
+            hills_twist: 1.0,
+            badlands_twist: 1.0,
+            sea_level: 0.0,
+            shelf_level: -0.375,
+            mountains_amount: 0.5,
+            hills_amount: 0.75,
+            badlands_amount: 0.3125,
+            terrain_offset: 1.0,
+            mountain_glaciation: 1.375,
+            river_depth: 0.0234375,
+            beach_height: 0.0625,
+            caves_frequency: 0.08,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_preset_is_the_default() {
+        assert_eq!(
+            WorldGenParams::load("./data/worldgen/default.ron").unwrap(),
+            WorldGenParams::default()
+        );
+    }
+
+    #[test]
+    fn saved_params_load_back() {
+        let params = WorldGenParams {
+            sea_level: 0.25,
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join("my_game_world_gen_params.ron");
+        params.save(&path).unwrap();
+        assert_eq!(WorldGenParams::load(&path).unwrap(), params);
+    }
+}
@@ -1,25 +1,96 @@
+use super::WorldGenParams;
+use crate::{Biome, BiomeMap, CHUNK_DIMENSIONS, TERRAIN_MARGIN, TerrainMap};
 use bevy::prelude::Resource;
-use noise::utils::NoiseMap;
 use noise::{core::worley::ReturnType, utils::*, *};
+use serde::{Deserialize, Serialize};
 
-#[derive(Resource, Debug)]
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct WorldMap {
     pub seed: u32,
     pub size: (usize, usize),
     pub x_bounds: (f64, f64),
     pub y_bounds: (f64, f64),
+    pub params: WorldGenParams,
+    /// Chunk coordinates where the colony starts
+    pub embark: (i32, i32),
 }
 
 impl WorldMap {
-    fn new(seed: u32, size: (usize, usize), x_bounds: (f64, f64), y_bounds: (f64, f64)) -> Self {
-        Self {
+    #[must_use]
+    pub fn new(
+        seed: u32,
+        size: (usize, usize),
+        x_bounds: (f64, f64),
+        y_bounds: (f64, f64),
+        params: WorldGenParams,
+    ) -> Self {
+        let mut world_map = Self {
             seed,
             size,
             x_bounds,
             y_bounds,
-        }
+            params,
+            embark: (0, 0),
+        };
+        world_map.embark = world_map.center_chunk();
+        world_map
     }
 
+    /// Chunk coordinates of the chunk in the middle of the map `size`
+    pub fn center_chunk(&self) -> (i32, i32) {
+        let (chunk_width, chunk_height, _) = CHUNK_DIMENSIONS;
+        (
+            self.size.0 as i32 / chunk_width / 2,
+            self.size.1 as i32 / chunk_height / 2,
+        )
+    }
+
+    /// Generates the elevations and biomes of the whole map, `size` values inside `x_bounds` and `y_bounds`
+    pub fn generate(&self) -> TerrainMap {
+        self.generate_area((0, 0), self.size)
+    }
+
+    /// Generates the elevations and biomes of the chunk at the `chunk` coordinates.
+    ///
+    /// It samples the same noise space than [`Self::generate`], where each chunk covers
+    /// `CHUNK_DIMENSIONS` values starting at `chunk * CHUNK_DIMENSIONS`. This way any chunk, even
+    /// outside the map `size`, can be generated on its own and matches its neighbours without seams.
+    pub fn generate_chunk(&self, (chunk_x, chunk_y): (i32, i32)) -> TerrainMap {
+        let (chunk_width, chunk_height, _) = CHUNK_DIMENSIONS;
+        self.generate_area(
+            (chunk_x * chunk_width, chunk_y * chunk_height),
+            (chunk_width as usize, chunk_height as usize),
+        )
+    }
+
+    /// Generates `size` values starting at the value `first` of the map, with the [`TERRAIN_MARGIN`]
+    /// around them. The values are as far apart as the ones of the map `size` inside its bounds.
+    fn generate_area(&self, (first_x, first_y): (i32, i32), size: (usize, usize)) -> TerrainMap {
+        let margin = TERRAIN_MARGIN as i32;
+        let (width, height) = (size.0 + 2 * TERRAIN_MARGIN, size.1 + 2 * TERRAIN_MARGIN);
+        let x_step = (self.x_bounds.1 - self.x_bounds.0) / self.size.0 as f64;
+        let y_step = (self.y_bounds.1 - self.y_bounds.0) / self.size.1 as f64;
+        let x_start = self.x_bounds.0 + x_step * f64::from(first_x - margin);
+        let y_start = self.y_bounds.0 + y_step * f64::from(first_y - margin);
+
+        self.with_planet(|planet| {
+            planet.build(
+                (width, height),
+                (x_start, x_start + x_step * width as f64),
+                (y_start, y_start + y_step * height as f64),
+            )
+        })
+    }
+
+    /// Builds the noise function that carves the caves, sampled at the tile positions
+    pub fn caves(&self) -> impl NoiseFn<f64, 3> {
+        Fbm::<Perlin>::new(self.seed + 150)
+            .set_frequency(self.params.caves_frequency)
+            .set_octaves(3)
+    }
+
+    /// Builds the planet noise functions and gives them to `sample`.
+    ///
     /// This example demonstrates how to use the noise-rs library to generate
     /// terrain elevations for a complex planetary surface.
     ///
@@ -77,91 +148,12 @@ impl WorldMap {
     /// A description for each group and subgroup can be found above the source
     /// code for that group and subgroup.
     #[allow(non_snake_case)]
-    pub fn generate(&self) -> NoiseMap {
-        /// Frequency of the planet's continents. Higher frequency produces
-        /// smaller, more numerous continents. This value is measured in radians.
-        const CONTINENT_FREQUENCY: f64 = 1.0;
-
-        /// Lacunarity of the planet's continents. Changing this value produces
-        /// slightly different continents. For the best results, this value should
-        /// be random, but close to 2.0.
-        const CONTINENT_LACUNARITY: f64 = 2.208984375;
-
-        /// Lacunarity of the planet's mountains. Changing the value produces
-        /// slightly different mountains. For the best results, this value should
-        /// be random, but close to 2.0.
-        const MOUNTAIN_LACUNARITY: f64 = 2.142578125;
-
-        /// Lacunarity of the planet's hills. Changing this value produces
-        /// slightly different hills. For the best results, this value should be
-        /// random, but close to 2.0.
-        const HILLS_LACUNARITY: f64 = 2.162109375;
-
-        /// Lacunarity of the planet's plains. Changing this value produces
-        /// slightly different plains. For the best results, this value should be
-        /// random, but close to 2.0.
-        const PLAINS_LACUNARITY: f64 = 2.314453125;
-
-        /// Lacunarity of the planet's badlands. Changing this value produces
-        /// slightly different badlands. For the best results, this value should
-        /// be random, but close to 2.0.
-        const BADLANDS_LACUNARITY: f64 = 2.212890625;
-
-        /// Specifies the "twistiness" of the mountains.
-        const MOUNTAINS_TWIST: f64 = 1.0;
-
-        /// Specifies the "twistiness" of the hills.
-        const HILLS_TWIST: f64 = 1.0;
-
-        /// Specifies the "twistiness" of the badlands.
-        const BADLANDS_TWIST: f64 = 1.0;
-
-        /// Specifies the planet's sea level. This value must be between -1.0
-        /// (minimum planet elevation) and +1.0 (maximum planet elevation).
-        const SEA_LEVEL: f64 = 0.0;
-
-        /// Specifies the level on the planet in which continental shelves appear.
-        /// This value must be between -1.0 (minimum planet elevation) and +1.0
-        /// (maximum planet elevation), and must be less than `SEA_LEVEL`.
-        const SHELF_LEVEL: f64 = -0.375;
-
-        /// Determines the amount of mountainous terrain that appears on the
-        /// planet. Values range from 0.0 (no mountains) to 1.0 (all terrain is
-        /// covered in mountains). Mountains terrain will overlap hilly terrain.
-        /// Because the badlands terrain may overlap parts of the mountainous
-        /// terrain, setting `MOUNTAINS_AMOUNT` to 1.0 may not completely cover the
-        /// terrain in mountains.
-        const MOUNTAINS_AMOUNT: f64 = 0.5;
-
-        /// Determines the amount of hilly terrain that appears on the planet.
-        /// Values range from 0.0 (no hills) to 1.0 (all terrain is covered in
-        /// hills). This value must be less than `MOUNTAINS_AMOUNT`. Because the
-        /// mountains terrain will overlap parts of the hilly terrain, and the
-        /// badlands terrain may overlap parts of the hilly terrain, setting
-        /// `HILLS_AMOUNT` to 1.0 may not completely cover the terrain in hills.
-        const HILLS_AMOUNT: f64 = (1.0 + MOUNTAINS_AMOUNT) / 2.0;
-
-        /// Determines the amount of badlands terrain that covers the planet.
-        /// Values range from 0.0 (no badlands) to 1.0 (all terrain is covered in
-        /// badlands). Badlands terrain will overlap any other type of terrain.
-        const BADLANDS_AMOUNT: f64 = 0.3125;
-
-        /// Offset to apply to the terrain type definition. Low values (< 1.0)
-        /// cause the rough areas to appear only at high elevations. High values
-        /// (> 2.0) cause the rough areas to appear at any elevation. The
-        /// percentage of rough areas on the planet are independent of this value.
-        const TERRAIN_OFFSET: f64 = 1.0;
-
-        /// Specifies the amount of "glaciation" on the mountains. This value
-        /// should be close to 1.0 and greater than 1.0.
-        const MOUNTAIN_GLACIATION: f64 = 1.375;
-
-        /// Scaling to apply to the base continent elevations, in planetary
-        /// elevation units.
-        const CONTINENT_HEIGHT_SCALE: f64 = (1.0 - SEA_LEVEL) / 4.0;
-
-        /// Maximum depth of the rivers, in planetary elevation units.
-        const RIVER_DEPTH: f64 = 0.0234375;
+    fn with_planet<R>(&self, sample: impl FnOnce(&Planet) -> R) -> R {
+        let params = &self.params;
+
+        // Scaling to apply to the base continent elevations, in planetary
+        // elevation units.
+        let continent_height_scale = (1.0 - params.sea_level) / 4.0;
 
         // ////////////////////////////////////////////////////////////////////////
         // Function group: continent definition
@@ -179,39 +171,39 @@ impl WorldMap {
         // -1.0 represents the lowest elevations and +1.0 represents the highest
         // elevations.
         //
-        fn baseContinentDef(seed: u32) -> impl NoiseFn<f64, 3> {
+        fn baseContinentDef(seed: u32, params: &WorldGenParams) -> impl NoiseFn<f64, 3> {
             // 1: [Continent module]: This FBM module generates the continents. This
             // noise function has a high number of octaves so that detail is visible at
             // high zoom levels.
             let baseContinentDef_fb0 = Fbm::<Perlin>::new(seed)
-                .set_frequency(CONTINENT_FREQUENCY)
+                .set_frequency(params.continent_frequency)
                 .set_persistence(0.5)
-                .set_lacunarity(CONTINENT_LACUNARITY)
+                .set_lacunarity(params.continent_lacunarity)
                 .set_octaves(14);
 
             // 2: [Continent-with-ranges module]: Next, a curve module modifies the
             // output value from the continent module so that very high values appear
             // near sea level. This defines the positions of the mountain ranges.
             let baseContinentDef_cu = Curve::new(baseContinentDef_fb0)
-                .add_control_point(-2.0000 + SEA_LEVEL, -1.625 + SEA_LEVEL)
-                .add_control_point(-1.0000 + SEA_LEVEL, -1.375 + SEA_LEVEL)
-                .add_control_point(0.0000 + SEA_LEVEL, -0.375 + SEA_LEVEL)
-                .add_control_point(0.0625 + SEA_LEVEL, 0.125 + SEA_LEVEL)
-                .add_control_point(0.1250 + SEA_LEVEL, 0.250 + SEA_LEVEL)
-                .add_control_point(0.2500 + SEA_LEVEL, 1.000 + SEA_LEVEL)
-                .add_control_point(0.5000 + SEA_LEVEL, 0.250 + SEA_LEVEL)
-                .add_control_point(0.7500 + SEA_LEVEL, 0.250 + SEA_LEVEL)
-                .add_control_point(1.0000 + SEA_LEVEL, 0.500 + SEA_LEVEL)
-                .add_control_point(2.0000 + SEA_LEVEL, 0.500 + SEA_LEVEL);
+                .add_control_point(-2.0000 + params.sea_level, -1.625 + params.sea_level)
+                .add_control_point(-1.0000 + params.sea_level, -1.375 + params.sea_level)
+                .add_control_point(0.0000 + params.sea_level, -0.375 + params.sea_level)
+                .add_control_point(0.0625 + params.sea_level, 0.125 + params.sea_level)
+                .add_control_point(0.1250 + params.sea_level, 0.250 + params.sea_level)
+                .add_control_point(0.2500 + params.sea_level, 1.000 + params.sea_level)
+                .add_control_point(0.5000 + params.sea_level, 0.250 + params.sea_level)
+                .add_control_point(0.7500 + params.sea_level, 0.250 + params.sea_level)
+                .add_control_point(1.0000 + params.sea_level, 0.500 + params.sea_level)
+                .add_control_point(2.0000 + params.sea_level, 0.500 + params.sea_level);
 
             // 3: [Carver module]: This higher-frequency BasicMulti module will be
             // used by subsequent noise functions to carve out chunks from the
             // mountain ranges within the continent-with-ranges module so that the
             // mountain ranges will not be completely impassible.
             let baseContinentDef_fb1 = Fbm::<Perlin>::new(seed + 1)
-                .set_frequency(CONTINENT_FREQUENCY * 4.34375)
+                .set_frequency(params.continent_frequency * 4.34375)
                 .set_persistence(0.5)
-                .set_lacunarity(CONTINENT_LACUNARITY)
+                .set_lacunarity(params.continent_lacunarity)
                 .set_octaves(11);
 
             // 4: [Scaled-carver module]: This scale/bias module scales the output
@@ -259,10 +251,10 @@ impl WorldMap {
         // 1: [Coarse-turbulence module]: This turbulence module warps the output
         // value from the base-continent-definition subgroup, adding some coarse
         // detail to it.
-        let continentDef_tu0 = Turbulence::<_, Perlin>::new(baseContinentDef(self.seed))
+        let continentDef_tu0 = Turbulence::<_, Perlin>::new(baseContinentDef(self.seed, params))
             .set_seed(self.seed + 10)
-            .set_frequency(CONTINENT_FREQUENCY * 15.25)
-            .set_power(CONTINENT_FREQUENCY / 113.75)
+            .set_frequency(params.continent_frequency * 15.25)
+            .set_power(params.continent_frequency / 113.75)
             .set_roughness(13);
 
         // 2: [Intermediate-turbulence module]: This turbulence module warps the
@@ -271,8 +263,8 @@ impl WorldMap {
         // adding some intermediate detail to it.
         let continentDef_tu1 = Turbulence::<_, Perlin>::new(continentDef_tu0)
             .set_seed(self.seed + 11)
-            .set_frequency(CONTINENT_FREQUENCY * 47.25)
-            .set_power(CONTINENT_FREQUENCY / 433.75)
+            .set_frequency(params.continent_frequency * 47.25)
+            .set_power(params.continent_frequency / 433.75)
             .set_roughness(12);
 
         // 3: [Warped-base-continent-definition module]: This turbulence module
@@ -281,8 +273,8 @@ impl WorldMap {
         // intermediate-turbulence module, adding some fine detail to it.
         let continentDef_tu2 = Turbulence::<_, Perlin>::new(continentDef_tu1)
             .set_seed(self.seed + 12)
-            .set_frequency(CONTINENT_FREQUENCY * 95.25)
-            .set_power(CONTINENT_FREQUENCY / 1019.75)
+            .set_frequency(params.continent_frequency * 95.25)
+            .set_power(params.continent_frequency / 1019.75)
             .set_roughness(11);
 
         // 4: [Select-turbulence module]: At this stage, the turbulence is applied
@@ -296,11 +288,11 @@ impl WorldMap {
         // definition subgroup become warped; the underwater and coastal areas
         // remain unaffected.
         let continentDef_se = Select::new(
-            baseContinentDef(self.seed),
+            baseContinentDef(self.seed, params),
             continentDef_tu2,
-            baseContinentDef(self.seed),
+            baseContinentDef(self.seed, params),
         )
-        .set_bounds(SEA_LEVEL - 0.0375, SEA_LEVEL + 1000.0375)
+        .set_bounds(params.sea_level - 0.0375, params.sea_level + 1000.0375)
         .set_falloff(0.0625);
 
         // 5: [Continent-definition group]: Caches the output value from the
@@ -335,8 +327,8 @@ impl WorldMap {
         // fjords.
         let terrainTypeDef_tu = Turbulence::<_, Perlin>::new(&continentDef)
             .set_seed(self.seed + 20)
-            .set_frequency(CONTINENT_FREQUENCY * 18.125)
-            .set_power(CONTINENT_FREQUENCY / 20.59375 * TERRAIN_OFFSET)
+            .set_frequency(params.continent_frequency * 18.125)
+            .set_power(params.continent_frequency / 20.59375 * params.terrain_offset)
             .set_roughness(3);
 
         // 2: [Roughness-probability-shift module]: This terracing module sharpens
@@ -346,7 +338,7 @@ impl WorldMap {
         // terrain.
         let terrainTypeDef_te = Terrace::new(terrainTypeDef_tu)
             .add_control_point(-1.00)
-            .add_control_point(SHELF_LEVEL + SEA_LEVEL / 2.0)
+            .add_control_point(params.shelf_level + params.sea_level / 2.0)
             .add_control_point(1.00);
 
         // 3: [Terrain-type-definition group]: Caches the output value from the
@@ -372,7 +364,7 @@ impl WorldMap {
         // generates the mountain ridges.
         let mountainBaseDef_rm0 = RidgedMulti::<Perlin>::new(self.seed + 30)
             .set_frequency(1723.0)
-            .set_lacunarity(MOUNTAIN_LACUNARITY)
+            .set_lacunarity(params.mountain_lacunarity)
             .set_octaves(4);
 
         // 2: [Scaled-mountain-ridge module]: Next, a scale/bias module scales the
@@ -389,7 +381,7 @@ impl WorldMap {
         // next step.
         let mountainBaseDef_rm1 = RidgedMulti::<Perlin>::new(self.seed + 31)
             .set_frequency(367.0)
-            .set_lacunarity(MOUNTAIN_LACUNARITY)
+            .set_lacunarity(params.mountain_lacunarity)
             .set_octaves(1);
 
         // 4: [Scaled-river-valley module]: Next, a scale/bias module applies a
@@ -419,7 +411,7 @@ impl WorldMap {
         let mountainBaseDef_tu0 = Turbulence::<_, Perlin>::new(mountainBaseDef_bl)
             .set_seed(self.seed + 32)
             .set_frequency(1337.0)
-            .set_power(1.0 / 6730.0 * MOUNTAINS_TWIST)
+            .set_power(1.0 / 6730.0 * params.mountains_twist)
             .set_roughness(4);
 
         // 8: [Warped-mountains-and-valleys module]: This turbulence module warps
@@ -429,7 +421,7 @@ impl WorldMap {
         let mountainBaseDef_tu1 = Turbulence::<_, Perlin>::new(mountainBaseDef_tu0)
             .set_seed(self.seed + 33)
             .set_frequency(21221.0)
-            .set_power(1.0 / 120157.0 * MOUNTAINS_TWIST)
+            .set_power(1.0 / 120157.0 * params.mountains_twist)
             .set_roughness(6);
 
         // 9: [Mountain-base-definition subgroup]: Caches the output value from the
@@ -451,7 +443,7 @@ impl WorldMap {
         // mountains.
         let mountainousHigh_rm0 = RidgedMulti::<Perlin>::new(self.seed + 40)
             .set_frequency(2371.0)
-            .set_lacunarity(MOUNTAIN_LACUNARITY)
+            .set_lacunarity(params.mountain_lacunarity)
             .set_octaves(3);
 
         // 2: [Mountain-basis-1 module]: This ridged-multifractal-noise function,
@@ -459,7 +451,7 @@ impl WorldMap {
         // mountains.
         let mountainousHigh_rm1 = RidgedMulti::<Perlin>::new(self.seed + 41)
             .set_frequency(2341.0)
-            .set_lacunarity(MOUNTAIN_LACUNARITY)
+            .set_lacunarity(params.mountain_lacunarity)
             .set_octaves(3);
 
         // 3: [High-mountains module]: Next, a maximum-value module causes more
@@ -474,7 +466,7 @@ impl WorldMap {
         let mountainousHigh_tu = Turbulence::<_, Perlin>::new(mountainousHigh_ma)
             .set_seed(self.seed + 42)
             .set_frequency(31511.0)
-            .set_power(1.0 / 180371.0 * MOUNTAINS_TWIST)
+            .set_power(1.0 / 180371.0 * params.mountains_twist)
             .set_roughness(4);
 
         // 5: [High-mountainous-terrain subgroup]: Caches the output value from the
@@ -496,7 +488,7 @@ impl WorldMap {
         // terrain.
         let mountainousLow_rm0 = RidgedMulti::<Perlin>::new(self.seed + 50)
             .set_frequency(1381.0)
-            .set_lacunarity(MOUNTAIN_LACUNARITY)
+            .set_lacunarity(params.mountain_lacunarity)
             .set_octaves(8);
 
         // 1: [Lowland-basis-1 module]: This ridged-multifractal-noise function,
@@ -504,7 +496,7 @@ impl WorldMap {
         // terrain.
         let mountainousLow_rm1 = RidgedMulti::<Perlin>::new(self.seed + 51)
             .set_frequency(1427.0)
-            .set_lacunarity(MOUNTAIN_LACUNARITY)
+            .set_lacunarity(params.mountain_lacunarity)
             .set_octaves(8);
 
         // 3: [Low-mountainous-terrain module]: This multiplication module combines
@@ -577,7 +569,7 @@ impl WorldMap {
         // smoothly increase towards higher elevations, as if a glacier ground out
         // those mountains. This exponential-curve module expects the output value
         // to range from -1.0 to +1.0.
-        let mountainousTerrain_ex = Exponent::new(mountainousTerrain_sb2).set_exponent(MOUNTAIN_GLACIATION);
+        let mountainousTerrain_ex = Exponent::new(mountainousTerrain_sb2).set_exponent(params.mountain_glaciation);
 
         let mountainousTerrain = Cache::new(mountainousTerrain_ex);
 
@@ -598,7 +590,7 @@ impl WorldMap {
         let hillyTerrain_bi = Billow::<Perlin>::new(self.seed + 60)
             .set_frequency(1663.0)
             .set_persistence(0.5)
-            .set_lacunarity(HILLS_LACUNARITY)
+            .set_lacunarity(params.hills_lacunarity)
             .set_octaves(6);
 
         // 2: [Scaled-hills module]: Next, a scale/bias module scales the output
@@ -614,7 +606,7 @@ impl WorldMap {
         // important in the next step.
         let hillyTerrain_rm = RidgedMulti::<Perlin>::new(self.seed + 61)
             .set_frequency(367.5)
-            .set_lacunarity(HILLS_LACUNARITY)
+            .set_lacunarity(params.hills_lacunarity)
             .set_octaves(1);
 
         // 4: [Scaled-river-valley module]: Next, a scale/bias module applies a
@@ -656,7 +648,7 @@ impl WorldMap {
         let hillyTerrain_tu0 = Turbulence::<_, Perlin>::new(hillyTerrain_ex)
             .set_seed(self.seed + 62)
             .set_frequency(1531.0)
-            .set_power(1.0 / 16921.0 * HILLS_TWIST)
+            .set_power(1.0 / 16921.0 * params.hills_twist)
             .set_roughness(4);
 
         // 10: [Warped-hilly-terrain module]: This turbulence module warps the
@@ -666,7 +658,7 @@ impl WorldMap {
         let hillyTerrain_tu1 = Turbulence::<_, Perlin>::new(hillyTerrain_tu0)
             .set_seed(self.seed + 63)
             .set_frequency(21617.0)
-            .set_power(1.0 / 117529.0 * HILLS_TWIST)
+            .set_power(1.0 / 117529.0 * params.hills_twist)
             .set_roughness(6);
 
         // 11: [Hilly-terrain group]: Caches the output value from the warped-hilly-
@@ -696,7 +688,7 @@ impl WorldMap {
         let plainsTerrain_bi0 = Billow::<Perlin>::new(self.seed + 70)
             .set_frequency(1097.5)
             .set_persistence(0.5)
-            .set_lacunarity(PLAINS_LACUNARITY)
+            .set_lacunarity(params.plains_lacunarity)
             .set_octaves(8);
 
         // 2: [Positive-plains-basis-0 module]: This scale/bias module makes the
@@ -710,7 +702,7 @@ impl WorldMap {
         let plainsTerrain_bi1 = Billow::<Perlin>::new(self.seed + 71)
             .set_frequency(1097.5)
             .set_persistence(0.5)
-            .set_lacunarity(PLAINS_LACUNARITY)
+            .set_lacunarity(params.plains_lacunarity)
             .set_octaves(8);
 
         // 4: [Positive-plains-basis-1 module]: This scale/bias module makes the
@@ -751,7 +743,7 @@ impl WorldMap {
         // octave, which makes very smooth dunes.
         let badlandsSand_rm = RidgedMulti::<Perlin>::new(self.seed + 80)
             .set_frequency(6163.5)
-            .set_lacunarity(BADLANDS_LACUNARITY)
+            .set_lacunarity(params.badlands_lacunarity)
             .set_octaves(1);
 
         // 2: [Scaled-sand-dunes module]: This scale/bias module shrinks the dune
@@ -793,9 +785,9 @@ impl WorldMap {
         // 1: [Cliff-basis module]: This Perlin-noise function generates some coherent
         // noise that will be used to generate the cliffs.
         let badlandsCliffs_fb = Fbm::<Perlin>::new(self.seed + 90)
-            .set_frequency(CONTINENT_FREQUENCY * 839.0)
+            .set_frequency(params.continent_frequency * 839.0)
             .set_persistence(0.5)
-            .set_lacunarity(BADLANDS_LACUNARITY)
+            .set_lacunarity(params.badlands_lacunarity)
             .set_octaves(6);
 
         // 2: [Cliff-shaping module]: Next, this curve module applies a curve to
@@ -833,7 +825,7 @@ impl WorldMap {
         let badlandsCliffs_tu0 = Turbulence::<_, Perlin>::new(badlandsCliffs_te)
             .set_seed(self.seed + 91)
             .set_frequency(16111.0)
-            .set_power(1.0 / 141539.0 * BADLANDS_TWIST)
+            .set_power(1.0 / 141539.0 * params.badlands_twist)
             .set_roughness(3);
 
         // 6: [Warped-cliffs module]: This turbulence module warps the output value
@@ -843,7 +835,7 @@ impl WorldMap {
         let badlandsCliffs_tu1 = Turbulence::<_, Perlin>::new(badlandsCliffs_tu0)
             .set_seed(self.seed + 92)
             .set_frequency(36107.0)
-            .set_power(1.0 / 211543.0 * BADLANDS_TWIST)
+            .set_power(1.0 / 211543.0 * params.badlands_twist)
             .set_roughness(3);
 
         // 7: [Badlands-cliffs subgroup]: Caches the output value from the warped-
@@ -898,7 +890,7 @@ impl WorldMap {
         // creates the large, deep rivers.
         let riverPositions_rm0 = RidgedMulti::<Perlin>::new(self.seed + 100)
             .set_frequency(18.75)
-            .set_lacunarity(CONTINENT_LACUNARITY)
+            .set_lacunarity(params.continent_lacunarity)
             .set_octaves(1);
 
         // 2: [Large-river-curve module]: This curve module applies a curve to the
@@ -918,7 +910,7 @@ impl WorldMap {
         // creates the small, shallow rivers.
         let riverPositions_rm1 = RidgedMulti::<Perlin>::new(self.seed + 101)
             .set_frequency(43.25)
-            .set_lacunarity(CONTINENT_LACUNARITY)
+            .set_lacunarity(params.continent_lacunarity)
             .set_octaves(1);
 
         // 4: [Small-river-curve module]: This curve module applies a curve to the
@@ -987,7 +979,7 @@ impl WorldMap {
         let scaledMountainousTerrain_fb = Fbm::<Perlin>::new(self.seed + 110)
             .set_frequency(14.5)
             .set_persistence(0.5)
-            .set_lacunarity(MOUNTAIN_LACUNARITY)
+            .set_lacunarity(params.mountain_lacunarity)
             .set_octaves(6);
 
         // 3: [Peak-modulation module]: This exponential-curve module applies an
@@ -1050,7 +1042,7 @@ impl WorldMap {
         let scaledHillyTerrain_fb = Fbm::<Perlin>::new(self.seed + 120)
             .set_frequency(13.5)
             .set_persistence(0.5)
-            .set_lacunarity(HILLS_LACUNARITY)
+            .set_lacunarity(params.hills_lacunarity)
             .set_octaves(6);
 
         // 3: [Hilltop-modulation module]: This exponential-curve module applies an
@@ -1163,21 +1155,21 @@ impl WorldMap {
         let continentalShelf_te = Terrace::new(&continentDef)
             .add_control_point(-1.0)
             .add_control_point(-0.75)
-            .add_control_point(SHELF_LEVEL)
+            .add_control_point(params.shelf_level)
             .add_control_point(1.0);
 
         // 2: [Clamped-sea-bottom module]: This clamping module clamps the output
         // value from the shelf-creator module so that its possible range is from
         // the bottom of the ocean to sea level. This is done because this subgroup
         // is only concerned about the oceans.
-        let continentalShelf_cl = Clamp::new(continentalShelf_te).set_bounds(-0.75, SEA_LEVEL);
+        let continentalShelf_cl = Clamp::new(continentalShelf_te).set_bounds(-0.75, params.sea_level);
 
         // 3: [Oceanic-trench-basis module]: This ridged-multifractal-noise function
         // generates some coherent noise that will be used to generate the oceanic
         // trenches. The ridges represent the bottom of the trenches.
         let continentalShelf_rm = RidgedMulti::<Perlin>::new(self.seed + 130)
-            .set_frequency(CONTINENT_FREQUENCY * 4.375)
-            .set_lacunarity(CONTINENT_LACUNARITY)
+            .set_frequency(params.continent_frequency * 4.375)
+            .set_lacunarity(params.continent_lacunarity)
             .set_octaves(16);
 
         // 4: [Oceanic-trench module]: This scale/bias module inverts the ridges
@@ -1209,7 +1201,7 @@ impl WorldMap {
         // scales the output value from the continent-definition group so that it
         // is measured in planetary elevation units.
         let baseContinentElev_sb = ScaleBias::new(&continentDef)
-            .set_scale(CONTINENT_HEIGHT_SCALE)
+            .set_scale(continent_height_scale)
             .set_bias(0.0);
 
         // 2: [Base-continent-with-oceans module]: This selector module applies the
@@ -1220,7 +1212,7 @@ impl WorldMap {
         // selects the output value from the base-scaled-continent-elevations
         // module.
         let baseContinentElev_se = Select::new(baseContinentElev_sb, continentalShelf, &continentDef)
-            .set_bounds(SHELF_LEVEL - 1000.0, SHELF_LEVEL)
+            .set_bounds(params.shelf_level - 1000.0, params.shelf_level)
             .set_falloff(0.03125);
 
         // 3: [Base-continent-elevation subgroup]: Caches the output value from the
@@ -1268,7 +1260,7 @@ impl WorldMap {
         // value. Otherwise, it selects the output value from the continents-with-
         // plains subgroup.
         let continentsWithHills_se = Select::new(&continentsWithPlains, &continentsWithHills_ad, &terrainTypeDef)
-            .set_bounds(1.0 - HILLS_AMOUNT, 1001.0 - HILLS_AMOUNT)
+            .set_bounds(1.0 - params.hills_amount, 1001.0 - params.hills_amount)
             .set_falloff(0.25);
 
         // 3: [Continents-with-hills subgroup]: Caches the output value from the
@@ -1299,7 +1291,7 @@ impl WorldMap {
         let continentsWithMountains_cu = Curve::new(&continentDef)
             .add_control_point(-1.0, -0.0625)
             .add_control_point(0.0, 0.0000)
-            .add_control_point(1.0 - MOUNTAINS_AMOUNT, 0.0625)
+            .add_control_point(1.0 - params.mountains_amount, 0.0625)
             .add_control_point(1.0, 0.2500);
 
         // 3: [Add-increased-mountain-heights module]: This addition module adds the
@@ -1315,7 +1307,7 @@ impl WorldMap {
         // continents-with-hills subgroup. Note that the continents-with-hills
         // subgroup also contains the plains terrain.
         let continentsWithMountains_se = Select::new(continentsWithHills, continentsWithMountains_ad1, &terrainTypeDef)
-            .set_bounds(1.0 - MOUNTAINS_AMOUNT, 1001.0 - MOUNTAINS_AMOUNT)
+            .set_bounds(1.0 - params.mountains_amount, 1001.0 - params.mountains_amount)
             .set_falloff(0.25);
 
         // 5: [Continents-with-mountains subgroup]: Caches the output value from the
@@ -1339,7 +1331,7 @@ impl WorldMap {
         let continentsWithBadlands_bm = Fbm::<Perlin>::new(self.seed + 140)
             .set_frequency(16.5)
             .set_persistence(0.5)
-            .set_lacunarity(CONTINENT_LACUNARITY)
+            .set_lacunarity(params.continent_lacunarity)
             .set_octaves(2);
 
         // 2: [Continents-and-badlands module]:  This addition module adds the
@@ -1361,7 +1353,7 @@ impl WorldMap {
             &continentsWithBadlands_ad,
             &continentsWithBadlands_bm,
         )
-        .set_bounds(1.0 - BADLANDS_AMOUNT, 1001.0 - BADLANDS_AMOUNT)
+        .set_bounds(1.0 - params.badlands_amount, 1001.0 - params.badlands_amount)
         .set_falloff(0.25);
 
         // 4: [Apply-badlands module]: This maximum-value module causes the badlands
@@ -1390,9 +1382,9 @@ impl WorldMap {
         // 1: [Scaled-rivers module]: This scale/bias module scales the output value
         // from the river-positions group so that it is measured in planetary
         // elevation units and is negative; this is required for step 2.
-        let continentsWithRivers_sb = ScaleBias::new(riverPositions)
-            .set_scale(RIVER_DEPTH / 2.0)
-            .set_bias(-RIVER_DEPTH / 2.0);
+        let continentsWithRivers_sb = ScaleBias::new(&riverPositions)
+            .set_scale(params.river_depth / 2.0)
+            .set_bias(-params.river_depth / 2.0);
 
         // 2: [Add-rivers-to-continents module]: This addition module adds the
         // rivers to the continents-with-badlands subgroup. Because the scaled-
@@ -1412,8 +1404,8 @@ impl WorldMap {
             continentsWithRivers_ad,
             &continentsWithBadlands,
         )
-        .set_bounds(SEA_LEVEL, CONTINENT_HEIGHT_SCALE + SEA_LEVEL)
-        .set_falloff(CONTINENT_HEIGHT_SCALE - SEA_LEVEL);
+        .set_bounds(params.sea_level, continent_height_scale + params.sea_level)
+        .set_falloff(continent_height_scale - params.sea_level);
 
         // 4: [Continents-with-rivers subgroup]: Caches the output value from the
         // blended-rivers-to-continents module.
@@ -1430,21 +1422,105 @@ impl WorldMap {
         //    continent-with-rivers subgroup.
         let unscaledFinalPlanet = Cache::new(continentsWithRivers);
 
-        PlaneMapBuilder::new(&unscaledFinalPlanet)
-            .set_size(self.size.0, self.size.1)
-            .set_x_bounds(self.x_bounds.0, self.x_bounds.1)
-            .set_y_bounds(self.y_bounds.0, self.y_bounds.1)
-            .build()
+        sample(&Planet {
+            elevation: &unscaledFinalPlanet,
+            terrain_type: &terrainTypeDef,
+            badlands_positions: &continentsWithBadlands_bm,
+            rivers: &riverPositions,
+            temperature: Fbm::<Perlin>::new(self.seed + 160).set_frequency(params.continent_frequency * 1.5),
+            moisture: Fbm::<Perlin>::new(self.seed + 170).set_frequency(params.continent_frequency * 2.0),
+            sea_level: params.sea_level,
+            beach_height: params.beach_height,
+            river_height: continent_height_scale,
+            mountains_amount: params.mountains_amount,
+            hills_amount: params.hills_amount,
+            badlands_amount: params.badlands_amount,
+        })
+    }
+}
+
+/// Value of the river positions noise under which the land is a river
+const RIVER_THRESHOLD: f64 = -0.5;
+
+/// Noise functions of the planet and the amounts of each terrain group, used to know which
+/// group is on each point of the elevations
+struct Planet<'a> {
+    elevation: &'a dyn NoiseFn<f64, 3>,
+    /// From -1.0 for the smoothest terrain to 1.0 for the roughest one
+    terrain_type: &'a dyn NoiseFn<f64, 3>,
+    badlands_positions: &'a dyn NoiseFn<f64, 3>,
+    /// From -2.0 in the middle of the rivers to 1.0 away from them
+    rivers: &'a dyn NoiseFn<f64, 3>,
+    temperature: Fbm<Perlin>,
+    moisture: Fbm<Perlin>,
+    sea_level: f64,
+    beach_height: f64,
+    /// Elevation over the sea level up to which the rivers are carved
+    river_height: f64,
+    mountains_amount: f64,
+    hills_amount: f64,
+    badlands_amount: f64,
+}
+
+impl Planet<'_> {
+    /// Samples `size` elevations and biomes inside `x_bounds` and `y_bounds`
+    fn build(&self, size: (usize, usize), x_bounds: (f64, f64), y_bounds: (f64, f64)) -> TerrainMap {
+        let elevations = PlaneMapBuilder::new(self.elevation)
+            .set_size(size.0, size.1)
+            .set_x_bounds(x_bounds.0, x_bounds.1)
+            .set_y_bounds(y_bounds.0, y_bounds.1)
+            .build();
+
+        // Same points than the `PlaneMapBuilder`
+        let x_step = (x_bounds.1 - x_bounds.0) / size.0 as f64;
+        let y_step = (y_bounds.1 - y_bounds.0) / size.1 as f64;
+        let mut biomes = BiomeMap::new(size.0, size.1);
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let point = [x_bounds.0 + x_step * x as f64, y_bounds.0 + y_step * y as f64, 0.0];
+                biomes.set_value(x, y, self.biome(point, elevations.get_value(x, y)));
+            }
+        }
+
+        TerrainMap {
+            elevations,
+            biomes,
+            sea_level: self.sea_level,
+        }
+    }
+
+    /// Classifies the `point` with the given `elevation`, the terrain groups go first and the rest
+    /// of the land is split by its climate
+    fn biome(&self, point: [f64; 3], elevation: f64) -> Biome {
+        if elevation < self.sea_level {
+            return Biome::Ocean;
+        }
+        if elevation < self.sea_level + self.beach_height {
+            return Biome::Beach;
+        }
+        // Same bounds than the selector carving the rivers into the continents
+        if elevation < self.sea_level + self.river_height && self.rivers.get(point) < RIVER_THRESHOLD {
+            return Biome::River;
+        }
+        // Same bounds than the selectors of the terrain groups, the badlands don't poke out of the mountains
+        let terrain_type = self.terrain_type.get(point);
+        if terrain_type > 1.0 - self.mountains_amount {
+            return Biome::Mountains;
+        }
+        if self.badlands_positions.get(point) > 1.0 - self.badlands_amount {
+            return Biome::Badlands;
+        }
+        if terrain_type > 1.0 - self.hills_amount {
+            return Biome::Hills;
+        }
+        // The higher the colder
+        let temperature = self.temperature.get(point) - (elevation - self.sea_level);
+        Biome::from_climate(temperature, self.moisture.get(point))
     }
 }
 
 impl Default for WorldMap {
     fn default() -> Self {
-        Self {
-            seed: 0,
-            size: (64, 64),
-            x_bounds: (-2.0, 2.0),
-            y_bounds: (-2.0, 2.0),
-        }
+        Self::new(0, (64, 64), (-2.0, 2.0), (-2.0, 2.0), WorldGenParams::default())
     }
 }
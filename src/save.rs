@@ -0,0 +1,466 @@
+// This lint usually gives bad advice in the context of Bevy -- hiding complex queries behind
+// type aliases tends to obfuscate code while offering no improvement in code cleanliness.
+#![allow(clippy::type_complexity)]
+
+use crate::{
+    Attributes, Backpack, Chasing, Chunk, Creature, CurrentMap, Direction, EntityId, EntityLinks, EntityRegistry,
+    Equipment, EquippedBy, Faction, FogOfWar, GameClock, GameState, Health, InBackpack, Item, MenuState,
+    PathfindingSteps, Position, RawMaster, SpawnType, SpriteRegistry, ViewRotation, Vision, WorldChunks,
+    WorldCreationState, WorldMap, split_map,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// Where the game in progress is saved, it is loaded back from the main menu
+pub const SAVE_FILE: &str = "./saves/save.ron";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<LoadGameFailed>()
+            .add_systems(Update, save_game_system.run_if(in_state(GameState::InGame)))
+            .add_systems(OnEnter(WorldCreationState::LoadGame), load_game_startup);
+    }
+}
+
+/// Sent when the game saved can not be loaded, to tell why on the main menu
+#[derive(Message, Debug)]
+pub struct LoadGameFailed(pub String);
+
+/// Everything needed to go on playing a game where it was left
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    /// The chunks that were not modified are generated again from it
+    pub world_map: WorldMap,
+    pub bounded: bool,
+    /// Chunks modified since they were generated, every chunk of a hand-authored map
+    pub chunks: Vec<Chunk>,
+    pub decorations: HashMap<(i32, i32), Vec<(String, Position)>>,
+    pub scattered: HashSet<(i32, i32)>,
+    /// Point of the world at the middle of the screen
+    pub camera: (f32, f32),
+    pub rotation: ViewRotation,
+    pub view_level: Option<i32>,
+    pub clock: GameClock,
+    /// Tiles revealed by each faction, what they see is computed again
+    pub revealed: HashMap<Faction, HashSet<Position>>,
+    /// Creatures and items, they refer to each other by their [`EntityId`]
+    pub entities: Vec<SavedEntity>,
+    /// The ids of the entities despawned before the save are not given again
+    pub next_entity_id: EntityId,
+}
+
+/// Where a saved creature or item is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SavedPlace {
+    At(Position),
+    /// In the backpack of the saved entity with this id
+    Carried(EntityId),
+    /// Held by the saved entity with this id
+    Equipped(EntityId),
+}
+
+/// A creature or an item, spawned again from its raw with the components that change while playing
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedEntity {
+    pub id: EntityId,
+    /// Name of its raw
+    pub name: String,
+    pub place: SavedPlace,
+    #[serde(default)]
+    pub direction: Option<Direction>,
+    #[serde(default)]
+    pub health: Option<Health>,
+    #[serde(default)]
+    pub attributes: Option<Attributes>,
+    #[serde(default)]
+    pub steps: Option<PathfindingSteps>,
+    /// Ids of the saved entities in its backpack
+    #[serde(default)]
+    pub backpack: Vec<EntityId>,
+    #[serde(default)]
+    pub holding_right_hand: Option<EntityId>,
+    #[serde(default)]
+    pub chasing: Option<EntityId>,
+}
+
+impl SaveGame {
+    /// Loads the game saved in the RON file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let ron_save = fs::read_to_string(path)?;
+        Ok(ron::from_str(&ron_save)?)
+    }
+
+    /// Saves the game in the RON file at `path`, creating its folder if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        if let Some(folder) = path.as_ref().parent() {
+            fs::create_dir_all(folder)?;
+        }
+        // The tiles of the chunks would take a line each
+        let config = ron::ser::PrettyConfig::default().compact_arrays(true);
+        fs::write(path, ron::ser::to_string_pretty(self, config)?)?;
+        Ok(())
+    }
+}
+
+/// State of the game kept in a save besides its creatures and items
+#[derive(SystemParam)]
+struct SavedState<'w, 's> {
+    world_map: ResMut<'w, WorldMap>,
+    world_chunks: ResMut<'w, WorldChunks>,
+    current_map: ResMut<'w, CurrentMap>,
+    clock: ResMut<'w, GameClock>,
+    fog: ResMut<'w, FogOfWar>,
+    registry: ResMut<'w, EntityRegistry>,
+    camera: Single<'w, 's, &'static mut Transform, With<Camera>>,
+}
+
+impl SavedState<'_, '_> {
+    fn save(&self, entities: Vec<SavedEntity>) -> SaveGame {
+        let world_chunks = &self.world_chunks;
+        // A hand-authored map can not be generated again
+        let chunks = world_chunks
+            .chunks
+            .iter()
+            .filter(|(coords, _)| world_chunks.bounded || world_chunks.modified.contains(coords))
+            .map(|(_, chunk)| chunk.clone())
+            .collect();
+        SaveGame {
+            world_map: self.world_map.clone(),
+            bounded: world_chunks.bounded,
+            chunks,
+            decorations: world_chunks.decorations.clone(),
+            scattered: world_chunks.scattered.clone(),
+            camera: (self.camera.translation.x, self.camera.translation.y),
+            rotation: self.current_map.layout.rotation,
+            view_level: self.current_map.layout.view_level,
+            clock: *self.clock,
+            revealed: self
+                .fog
+                .visions
+                .iter()
+                .map(|(faction, vision)| (*faction, vision.revealed.clone()))
+                .collect(),
+            entities,
+            next_entity_id: self.registry.next_id(),
+        }
+    }
+
+    /// Puts the world back the way it was saved, its tiles are streamed around the camera
+    fn restore(&mut self, save: &SaveGame) {
+        *self.world_map = save.world_map.clone();
+        let world_chunks = &mut *self.world_chunks;
+        world_chunks.chunks = if save.bounded {
+            HashMap::new()
+        } else {
+            split_map(&save.world_map.generate(), &save.world_map.caves())
+        };
+        world_chunks.modified = save.chunks.iter().map(|chunk| (chunk.x, chunk.y)).collect();
+        world_chunks
+            .chunks
+            .extend(save.chunks.iter().map(|chunk| ((chunk.x, chunk.y), chunk.clone())));
+        world_chunks.decorations = save.decorations.clone();
+        world_chunks.scattered = save.scattered.clone();
+        world_chunks.bounded = save.bounded;
+
+        self.current_map.layout.rotate(save.rotation);
+        self.current_map.layout.view_level = save.view_level;
+        self.camera.translation.x = save.camera.0;
+        self.camera.translation.y = save.camera.1;
+        *self.clock = save.clock;
+        self.fog.visions = save
+            .revealed
+            .iter()
+            .map(|(faction, revealed)| {
+                let vision = Vision {
+                    revealed: revealed.clone(),
+                    visible: HashSet::new(),
+                };
+                (*faction, vision)
+            })
+            .collect();
+        // The entities are given their saved ids back as they are spawned
+        *self.registry = EntityRegistry::starting_at(save.next_entity_id);
+    }
+
+    /// Spawns the saved creatures and items again from their raws, with their saved ids
+    fn spawn_entities(
+        &mut self,
+        save: &SaveGame,
+        commands: &mut Commands,
+        raw_master: &RawMaster,
+        sprites: &SpriteRegistry,
+    ) {
+        // The creatures are spawned before the items they carry
+        for on_the_map in [true, false] {
+            for saved in &save.entities {
+                let owner = |owner: EntityId| self.registry.entity(owner);
+                let pos = match saved.place {
+                    SavedPlace::At(Position { x, y, z }) if on_the_map => SpawnType::AtPosition { x, y, z },
+                    SavedPlace::Carried(id) if !on_the_map => match owner(id) {
+                        Some(by) => SpawnType::Carried { by },
+                        None => continue,
+                    },
+                    SavedPlace::Equipped(id) if !on_the_map => match owner(id) {
+                        Some(by) => SpawnType::Equipped { by },
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                match raw_master.spawn_named_entity(commands, sprites, &mut self.current_map, saved.name.clone(), pos) {
+                    Some(entity) => {
+                        commands.entity(entity).insert(saved.id);
+                        self.registry.insert(saved.id, entity);
+                    }
+                    None => warn!("{} of the saved game is not in the raws", saved.name),
+                }
+            }
+        }
+
+        // What changed while playing replaces what the raws give them
+        let registry = &*self.registry;
+        for saved in &save.entities {
+            let Some(spawned_entity) = registry.entity(saved.id) else { continue };
+            let mut entity_commands = commands.entity(spawned_entity);
+            if let Some(direction) = saved.direction {
+                entity_commands.insert(direction);
+            }
+            if let Some(health) = &saved.health {
+                entity_commands.insert(health.clone());
+            }
+            if let Some(attributes) = &saved.attributes {
+                entity_commands.insert(attributes.clone());
+            }
+            if let Some(steps) = &saved.steps {
+                entity_commands.insert(steps.clone());
+            }
+            if !saved.backpack.is_empty()
+                && let Some(backpack) = Backpack::from_ids(&saved.backpack, registry)
+            {
+                entity_commands.insert(backpack);
+            }
+            if saved.holding_right_hand.is_some()
+                && let Some(equipment) = Equipment::from_ids(&saved.holding_right_hand, registry)
+            {
+                entity_commands.insert(equipment);
+            }
+            if let Some(chasing) = saved.chasing.and_then(|target| Chasing::from_ids(&target, registry)) {
+                entity_commands.insert(chasing);
+            }
+        }
+    }
+}
+
+// This system writes the game in progress to the save file when F5 is pressed
+fn save_game_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: SavedState,
+    entities_query: Query<
+        (
+            &EntityId,
+            &Name,
+            AnyOf<(&Position, &InBackpack, &EquippedBy)>,
+            (
+                Option<&Direction>,
+                Option<&Health>,
+                Option<&Attributes>,
+                Option<&PathfindingSteps>,
+            ),
+            (Option<&Backpack>, Option<&Equipment>, Option<&Chasing>),
+        ),
+        Or<(With<Creature>, With<Item>)>,
+    >,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F5) {
+        return;
+    }
+
+    let registry = &*state.registry;
+    let mut entities = Vec::new();
+    for (id, name, place, (direction, health, attributes, steps), (backpack, equipment, chasing)) in &entities_query {
+        let place = match place {
+            (Some(pos), ..) => Some(SavedPlace::At(*pos)),
+            (_, Some(in_backpack), _) => in_backpack.to_ids(registry).map(SavedPlace::Carried),
+            (.., Some(equipped_by)) => equipped_by.to_ids(registry).map(SavedPlace::Equipped),
+            (None, None, None) => unreachable!("the query asks for one of them"),
+        };
+        // Carried by something that can not be saved
+        let Some(place) = place else { continue };
+        entities.push(SavedEntity {
+            id: *id,
+            name: name.to_string(),
+            place,
+            direction: direction.copied(),
+            health: health.cloned(),
+            attributes: attributes.cloned(),
+            steps: steps.cloned(),
+            backpack: backpack
+                .and_then(|backpack| backpack.to_ids(registry))
+                .unwrap_or_default(),
+            holding_right_hand: equipment.and_then(|equipment| equipment.to_ids(registry)).flatten(),
+            chasing: chasing.and_then(|chasing| chasing.to_ids(registry)),
+        });
+    }
+
+    match state.save(entities).save(SAVE_FILE) {
+        Ok(()) => info!("Game saved to {}", SAVE_FILE),
+        Err(err) => warn!("Unable to save the game to {}: {}", SAVE_FILE, err),
+    }
+}
+
+fn load_game_startup(
+    mut commands: Commands,
+    mut map_creation_state: ResMut<NextState<WorldCreationState>>,
+    mut app_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    raw_master: Res<RawMaster>,
+    sprite_registry: Res<SpriteRegistry>,
+    mut state: SavedState,
+) {
+    map_creation_state.set(WorldCreationState::Disabled);
+    let save = match SaveGame::load(SAVE_FILE) {
+        Ok(save) => save,
+        Err(err) => {
+            warn!("Unable to load the game {}: {}", SAVE_FILE, err);
+            commands.write_message(LoadGameFailed(format!("Unable to load the game: {err}")));
+            return;
+        }
+    };
+    state.restore(&save);
+
+    state.spawn_entities(&save, &mut commands, &raw_master, &sprite_registry);
+
+    menu_state.set(MenuState::Disabled);
+    app_state.set(GameState::InGame);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WorldGenParams;
+    use crate::spawner::game_world;
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// A creature carrying an item on a modified chunk
+    fn save_game() -> SaveGame {
+        let mut chunk = Chunk::empty(1, -2);
+        chunk
+            .tile_names
+            .insert(Position::new(16, -32, 4), "StoneFloor".to_string());
+        let dummy = SavedEntity {
+            id: EntityId(0),
+            name: "Dummy".to_string(),
+            place: SavedPlace::At(Position::new(16, -32, 5)),
+            direction: Some(Direction::default()),
+            health: Some(Health { current: 3, max: 10 }),
+            attributes: None,
+            steps: Some(PathfindingSteps::default()),
+            backpack: vec![EntityId(3)],
+            holding_right_hand: None,
+            chasing: None,
+        };
+        SaveGame {
+            world_map: WorldMap::new(7, (64, 64), (0.0, 1.0), (0.0, 1.0), WorldGenParams::default()),
+            bounded: false,
+            chunks: vec![chunk],
+            decorations: HashMap::from([((1, -2), vec![("Tree".to_string(), Position::new(17, -32, 5))])]),
+            scattered: HashSet::from([(1, -2), (0, 0)]),
+            camera: (120.0, -48.5),
+            rotation: ViewRotation::Deg90,
+            view_level: Some(12),
+            clock: GameClock::default(),
+            revealed: HashMap::from([(Faction::Colony, HashSet::from([Position::new(16, -32, 5)]))]),
+            entities: vec![
+                dummy,
+                SavedEntity {
+                    id: EntityId(3),
+                    name: "Heart".to_string(),
+                    place: SavedPlace::Carried(EntityId(0)),
+                    direction: None,
+                    health: None,
+                    attributes: None,
+                    steps: None,
+                    backpack: Vec::new(),
+                    holding_right_hand: None,
+                    chasing: None,
+                },
+            ],
+            next_entity_id: EntityId(5),
+        }
+    }
+
+    #[test]
+    fn saved_games_load_back() {
+        let save = save_game();
+        let path = std::env::temp_dir().join("my_game_save.ron");
+        save.save(&path).unwrap();
+        assert_eq!(SaveGame::load(&path).unwrap(), save);
+    }
+
+    #[test]
+    fn saved_games_are_restored_into_the_world() {
+        let mut world = game_world();
+        world.init_resource::<WorldMap>();
+        world.init_resource::<WorldChunks>();
+        world.init_resource::<GameClock>();
+        world.init_resource::<FogOfWar>();
+        world.spawn((Camera::default(), Transform::default()));
+
+        // A hand-authored map is not generated again
+        let save = SaveGame {
+            bounded: true,
+            ..save_game()
+        };
+        let restored = save.clone();
+        world
+            .run_system_once(
+                move |mut commands: Commands,
+                      raw_master: Res<RawMaster>,
+                      sprites: Res<SpriteRegistry>,
+                      mut state: SavedState| {
+                    state.restore(&restored);
+                    state.spawn_entities(&restored, &mut commands, &raw_master, &sprites);
+                },
+            )
+            .unwrap();
+
+        let world_chunks = world.resource::<WorldChunks>();
+        assert_eq!(world_chunks.chunks.get(&(1, -2)), Some(&save.chunks[0]));
+        assert_eq!(world_chunks.modified, HashSet::from([(1, -2)]));
+        assert_eq!(world_chunks.scattered, save.scattered);
+        assert!(world_chunks.bounded);
+        assert_eq!(world.resource::<CurrentMap>().layout.view_level, Some(12));
+        assert_eq!(
+            world.resource::<FogOfWar>().visions[&Faction::Colony].revealed,
+            save.revealed[&Faction::Colony]
+        );
+        let camera = world
+            .query_filtered::<&Transform, With<Camera>>()
+            .single(&world)
+            .unwrap();
+        assert_eq!((camera.translation.x, camera.translation.y), save.camera);
+
+        // The creature and its item are spawned with their ids and linked through them
+        let registry = world.resource::<EntityRegistry>();
+        assert_eq!(registry.next_id(), EntityId(5));
+        let dummy = registry.entity(EntityId(0)).unwrap();
+        let heart = registry.entity(EntityId(3)).unwrap();
+        assert_eq!(world.get::<Name>(dummy).unwrap().as_str(), "Dummy");
+        assert_eq!(world.get::<Position>(dummy), Some(&Position::new(16, -32, 5)));
+        assert_eq!(world.get::<Health>(dummy), Some(&Health { current: 3, max: 10 }));
+        assert_eq!(world.get::<EntityId>(dummy), Some(&EntityId(0)));
+        assert_eq!(world.get::<Backpack>(dummy).unwrap().content, HashSet::from([heart]));
+        assert_eq!(world.get::<Name>(heart).unwrap().as_str(), "Heart");
+        assert_eq!(world.get::<InBackpack>(heart), Some(&InBackpack { owner: dummy }));
+        assert_eq!(
+            world.resource::<CurrentMap>().entities.get(&Position::new(16, -32, 5)),
+            &[dummy]
+        );
+    }
+}
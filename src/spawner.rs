@@ -1,12 +1,14 @@
-use crate::CurrentMap;
 use crate::raws::*;
-use bevy::prelude::{App, AssetServer, Commands, Message, MessageReader, Plugin, PreUpdate, Res, ResMut};
+use crate::{CurrentMap, EntityRegistry, GameState};
+use bevy::prelude::{App, Commands, Message, MessageReader, OnExit, Plugin, PreUpdate, Res, ResMut};
 
 pub struct SpawnerPlugin;
 
 impl Plugin for SpawnerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_message::<SpawnEntity>().add_systems(PreUpdate, spawn_entity);
+        app.add_message::<SpawnEntity>()
+            .add_systems(PreUpdate, spawn_entity)
+            .add_systems(OnExit(GameState::InGame), clear_entity_registry);
     }
 }
 
@@ -19,15 +21,45 @@ pub struct SpawnEntity {
 pub fn spawn_entity(
     mut events: MessageReader<SpawnEntity>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    sprite_registry: Res<SpriteRegistry>,
     mut current_map: ResMut<CurrentMap>,
     raw_master: Res<RawMaster>,
+    mut registry: ResMut<EntityRegistry>,
 ) {
     for ev in events.read() {
-        if let Some(_entity) =
-            raw_master.spawn_named_entity(&mut commands, &asset_server, &mut current_map, ev.name.clone(), ev.pos)
-        {
-            // TODO put the entity somewhere?
+        if let Some(entity) = raw_master.spawn_named_entity(
+            &mut commands,
+            &sprite_registry,
+            &mut current_map,
+            ev.name.clone(),
+            ev.pos,
+        ) {
+            // The tiles are saved with their chunk, only the creatures and items need an id
+            if !raw_master.tile_index.contains_key(&ev.name) {
+                commands.entity(entity).insert(registry.register(entity));
+            }
         }
     }
 }
+
+/// The ids belong to the game left, a new one gives them from zero again
+pub fn clear_entity_registry(mut registry: ResMut<EntityRegistry>) {
+    *registry = EntityRegistry::default();
+}
+
+/// World of a game with the raws loaded, for the tests spawning entities in it
+#[cfg(test)]
+pub(crate) fn game_world() -> bevy::prelude::World {
+    let mut world = bevy::prelude::World::new();
+    let mut raw_master = RawMaster {
+        raws: read_raws(),
+        ..Default::default()
+    };
+    raw_master.load();
+    world.insert_resource(raw_master);
+    world.init_resource::<SpriteRegistry>();
+    world.init_resource::<EntityRegistry>();
+    world.init_resource::<CurrentMap>();
+    world.init_resource::<bevy::ecs::message::Messages<SpawnEntity>>();
+    world
+}
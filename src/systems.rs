@@ -1,4 +1,4 @@
-use crate::GameState;
+use crate::{GameState, IsPaused, click_system};
 use bevy::prelude::*;
 
 mod ai_system;
@@ -11,6 +11,11 @@ mod visibility_system;
 use visibility_system::*;
 mod field_of_view_system;
 use field_of_view_system::*;
+mod fog_of_war_system;
+pub(crate) use fog_of_war_system::fog_visibility_system;
+use fog_of_war_system::*;
+mod lighting_system;
+use lighting_system::*;
 mod chasing_system;
 use chasing_system::*;
 mod reaction_system;
@@ -25,9 +30,16 @@ impl Plugin for SystemsPlugin {
         app.add_systems(
             Update,
             (
+                cursor_highlight_system,
+                click_system,
                 chasing_system,
+                ambient_light_system,
+                light_map_system,
                 field_of_view_system,
+                fog_of_war_system,
+                fog_visibility_system,
                 visibility_system,
+                light_sprites_system,
                 viewshed_highlight_system,
                 reaction_system,
                 position_check_system,
@@ -35,6 +47,8 @@ impl Plugin for SystemsPlugin {
                 .chain()
                 .run_if(in_state(GameState::InGame)),
         )
-        .add_systems(FixedUpdate, move_system.run_if(in_state(GameState::InGame)));
+        .add_systems(FixedUpdate, move_system.run_if(in_state(GameState::InGame)))
+        .add_systems(FixedUpdate, clock_system.run_if(in_state(IsPaused::Running)))
+        .add_systems(OnExit(GameState::InGame), (clear_fog_of_war, clear_lighting));
     }
 }
@@ -1,22 +1,46 @@
-use crate::{fov, Creature, Direction, CurrentMap, Position, Viewshed};
-use bevy::prelude::{Changed, Or, Query, Res, Transform, With};
+use crate::{fov, Creature, Direction, CurrentMap, LightChanged, LightMap, Position, Viewshed};
+use bevy::prelude::{DetectChanges, MessageReader, Query, Ref, Res, Transform, With};
+
+/// Part of its range a creature still sees in the dark
+const DARK_SIGHT: f32 = 0.25;
 
 #[allow(clippy::type_complexity)]
 pub fn field_of_view_system(
-    mut query: Query<
-        (&Position, &mut Viewshed, &Direction),
-        (With<Creature>, Or<(Changed<Transform>, Changed<Direction>)>),
-    >,
+    mut query: Query<(&Position, &mut Viewshed, Ref<Direction>, Ref<Transform>), With<Creature>>,
     grid: Res<CurrentMap>,
+    light_map: Res<LightMap>,
+    mut light_changed: MessageReader<LightChanged>,
 ) {
-    // This should only be triggered when the creature moves, either to another tile or facing direction
-    for (pos, mut viewshed, direction) in query.iter_mut() {
-        viewshed.visible_tiles = fov(
+    // The light of the sky reaches every creature, the one of the tiles only those in range of them
+    let mut ambient = false;
+    let mut relit_tiles = Vec::new();
+    for changed in light_changed.read() {
+        match changed {
+            LightChanged::Ambient => ambient = true,
+            LightChanged::Tiles(positions) => relit_tiles.extend(positions.iter().copied()),
+        }
+    }
+    // This should only be triggered when the creature moves, either to another tile or facing direction,
+    // or when the terrain it sees changes, or the light
+    for (pos, mut viewshed, direction, transform) in query.iter_mut() {
+        let relit = ambient || relit_tiles.iter().any(|tile| pos.unsigned_distance_to(*tile) <= viewshed.range);
+        if !(relit || viewshed.is_changed() || direction.is_changed() || transform.is_changed()) {
+            continue;
+        }
+        let visible_tiles = fov(
             *pos,
             viewshed.range,
             *direction,
             (viewshed.angle as f32).to_radians(),
-            |h| grid.blocked_coords.contains(&h) || !grid.tiles.contains_key(&h),
+            |h| grid.blocks_sight(h),
         );
+        // The darker a tile is the closer it has to be to be seen
+        let range = viewshed.range as f32;
+        viewshed.visible_tiles = visible_tiles
+            .into_iter()
+            .filter(|target| {
+                pos.unsigned_distance_to(*target) as f32 <= range * light_map.brightness(target).max(DARK_SIGHT)
+            })
+            .collect();
     }
 }
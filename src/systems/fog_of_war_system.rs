@@ -0,0 +1,55 @@
+use crate::{Creature, CurrentMap, CursorHighlight, Faction, FogChanged, FogOfWar, Position, Viewshed};
+use bevy::prelude::{
+    Changed, DetectChangesMut, Has, MessageWriter, Query, RemovedComponents, Res, ResMut, Sprite, Visibility, With,
+};
+use std::collections::{HashMap, HashSet};
+
+pub fn fog_of_war_system(
+    changed_query: Query<(), (With<Creature>, Changed<Viewshed>)>,
+    mut removed: RemovedComponents<Viewshed>,
+    viewshed_query: Query<(&Viewshed, &Faction), With<Creature>>,
+    current_map: Res<CurrentMap>,
+    mut fog: ResMut<FogOfWar>,
+    mut fog_changed: MessageWriter<FogChanged>,
+) {
+    // Only what a creature sees changes the vision of its faction, or a creature gone
+    if changed_query.is_empty() && removed.read().count() == 0 {
+        return;
+    }
+
+    // The ground under the tiles seen is seen with them, and the tiles behind them whose front
+    // faces the camera shows
+    let [left, right] = current_map.layout.rotation.front_steps();
+    let mut visible: HashMap<Faction, HashSet<Position>> =
+        fog.visions.keys().map(|faction| (*faction, HashSet::new())).collect();
+    for (viewshed, faction) in &viewshed_query {
+        let seen = visible.entry(*faction).or_default();
+        for pos in &viewshed.visible_tiles {
+            seen.insert(*pos);
+            seen.extend([*pos - Position::new(0, 0, 1), *pos - left, *pos - right]);
+        }
+    }
+
+    for (faction, seen) in visible {
+        let changed = fog.update(faction, seen);
+        if faction == fog.shown && !changed.is_empty() {
+            fog_changed.write(FogChanged(changed));
+        }
+    }
+}
+
+/// Hides the creatures, items and highlights out of sight of the faction shown, but the cursor
+pub fn fog_visibility_system(
+    fog: Res<FogOfWar>,
+    mut sprites_query: Query<(&Position, &mut Visibility, Has<CursorHighlight>), With<Sprite>>,
+) {
+    for (pos, mut visibility, is_cursor) in &mut sprites_query {
+        let hidden = !(is_cursor || fog.is_visible(pos));
+        visibility.set_if_neq(if hidden { Visibility::Hidden } else { Visibility::Inherited });
+    }
+}
+
+/// The visions belong to the map left, a new game starts unexplored
+pub fn clear_fog_of_war(mut fog: ResMut<FogOfWar>) {
+    fog.visions.clear();
+}
@@ -1,7 +1,22 @@
-use crate::{Creature, Position, SpawnEntity, SpawnType, Viewshed, ViewshedHighlight};
+use crate::{
+    Creature, CursorHighlight, CursorTile, Position, SpawnEntity, SpawnType, Viewshed, ViewshedHighlight,
+    highlight_tile,
+};
 use bevy::prelude::{Changed, Commands, Entity, MessageWriter, Query, With};
 use std::collections::HashSet;
 
+/// Highlights the tile drawn on top under the cursor
+pub fn cursor_highlight_system(
+    mut commands: Commands,
+    mut spawn_event: MessageWriter<SpawnEntity>,
+    cursor: CursorTile,
+    highlighted_query: Query<(Entity, &Position), With<CursorHighlight>>,
+) {
+    if let Some(pos) = cursor.tile() {
+        highlight_tile(&pos, &mut commands, &mut spawn_event, &highlighted_query);
+    }
+}
+
 pub fn viewshed_highlight_system(
     mut commands: Commands,
     mut spawn_event: MessageWriter<SpawnEntity>,
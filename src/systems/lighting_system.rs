@@ -0,0 +1,185 @@
+use crate::{
+    CurrentMap, CursorHighlight, Direction, GameClock, LightChanged, LightMap, LightSource, Position, Tile,
+    ViewRotation, ViewshedHighlight, fov, light_falloff,
+};
+use bevy::prelude::{
+    Added, DetectChanges, DetectChangesMut, Entity, Local, MessageWriter, Query, Ref, RemovedComponents, Res, ResMut,
+    Sprite, Time, Without,
+};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+/// Moves the in-game clock forward while the game runs
+pub fn clock_system(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    clock.advance(time.delta_secs());
+}
+
+/// Sets the light of the sky from the time of the day
+pub fn ambient_light_system(
+    clock: Res<GameClock>,
+    mut light_map: ResMut<LightMap>,
+    mut light_changed: MessageWriter<LightChanged>,
+) {
+    // The map is only flagged as changed when the light does, the sight of the creatures follows it
+    if light_map.bypass_change_detection().set_ambient(clock.sun_level()) {
+        light_map.set_changed();
+        light_changed.write(LightChanged::Ambient);
+    }
+}
+
+/// What the light sources were lit for, the side of the view and the spawned map tiles
+#[derive(Default)]
+pub struct LitTerrain {
+    rotation: ViewRotation,
+    /// Position of each spawned map tile, to know where the despawned ones were
+    tiles: HashMap<Entity, Position>,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn light_map_system(
+    current_map: Res<CurrentMap>,
+    lights_query: Query<(Entity, Ref<Position>, Ref<LightSource>)>,
+    tiles_query: Query<(Entity, &Position), (Added<Tile>, Without<CursorHighlight>, Without<ViewshedHighlight>)>,
+    mut removed_tiles: RemovedComponents<Tile>,
+    mut lit_terrain: Local<LitTerrain>,
+    mut light_map: ResMut<LightMap>,
+    mut light_changed: MessageWriter<LightChanged>,
+) {
+    // The tiles spawned or despawned change what the lights around them reach, like the ones a
+    // chunk streamed in or out, a dug or built tile, or the tiles culled from another side of the view
+    let mut changed_tiles: Vec<Position> = Vec::new();
+    for (entity, pos) in &tiles_query {
+        lit_terrain.tiles.insert(entity, *pos);
+        changed_tiles.push(*pos);
+    }
+    changed_tiles.extend(
+        removed_tiles
+            .read()
+            .filter_map(|entity| lit_terrain.tiles.remove(&entity)),
+    );
+    // The faces lit are the ones the camera shows
+    let rotation = current_map.layout.rotation;
+    let rotated = lit_terrain.rotation != rotation;
+    lit_terrain.rotation = rotation;
+
+    // A light source picked up or despawned loses its position
+    let mut changed = false;
+    light_map.bypass_change_detection().sources.retain(|entity, _| {
+        let kept = lights_query.contains(*entity);
+        changed |= !kept;
+        kept
+    });
+
+    // Each light source lights what it could see all around it, and the ground and the front faces
+    // of those tiles like the fog of war does
+    let [left, right] = rotation.front_steps();
+    for (entity, pos, source) in &lights_query {
+        let touched = changed_tiles
+            .iter()
+            .any(|tile| pos.unsigned_distance_to(*tile) <= source.range + 1);
+        if !(rotated || touched || pos.is_changed() || source.is_changed()) {
+            continue;
+        }
+        let mut strengths: HashMap<Position, f32> = HashMap::new();
+        let reached = fov(*pos, source.range, Direction::default(), TAU, |h| {
+            current_map.blocks_sight(h)
+        });
+        for target in reached {
+            let strength = light_falloff(pos.unsigned_distance_to(target), source.range);
+            for lit in [target, target - Position::new(0, 0, 1), target - left, target - right] {
+                let lit_strength = strengths.entry(lit).or_default();
+                *lit_strength = lit_strength.max(strength);
+            }
+        }
+        let lit = strengths
+            .into_iter()
+            .map(|(lit, strength)| (lit, source.color * strength))
+            .collect();
+        light_map.bypass_change_detection().sources.insert(entity, lit);
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+
+    let changed = light_map.bypass_change_detection().add_up_sources();
+    if !changed.is_empty() {
+        light_map.set_changed();
+        light_changed.write(LightChanged::Tiles(changed));
+    }
+}
+
+/// Tints the creatures and items with the light of their tile, the highlights keep their color
+#[allow(clippy::type_complexity)]
+pub fn light_sprites_system(
+    light_map: Res<LightMap>,
+    mut sprites_query: Query<(&Position, &mut Sprite), (Without<CursorHighlight>, Without<ViewshedHighlight>)>,
+) {
+    for (pos, mut sprite) in &mut sprites_query {
+        let color = light_map.light(pos).into();
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// The clock and the lights belong to the game left, a new one starts in the morning
+pub fn clear_lighting(mut clock: ResMut<GameClock>, mut light_map: ResMut<LightMap>) {
+    *clock = GameClock::default();
+    *light_map = LightMap::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TileType;
+    use bevy::ecs::message::Messages;
+    use bevy::prelude::{LinearRgba, Schedule, World};
+
+    #[test]
+    fn lights_are_lit_again_when_the_tiles_around_them_change() {
+        let mut world = World::new();
+        world.init_resource::<LightMap>();
+        world.init_resource::<Messages<LightChanged>>();
+        let mut current_map = CurrentMap::default();
+        for x in 0..6 {
+            current_map.tile_types.insert(Position::new(x, 0, 0), TileType::Floor);
+        }
+        world.insert_resource(current_map);
+        world.spawn((
+            Position::ZERO,
+            LightSource {
+                range: 4,
+                color: LinearRgba::WHITE,
+            },
+        ));
+        let mut schedule = Schedule::default();
+        schedule.add_systems(light_map_system);
+        schedule.run(&mut world);
+        let behind = Position::new(3, 0, 0);
+        assert!(world.resource::<LightMap>().lights.contains_key(&behind));
+        // Only the tile below and the faces in front of the camera are lit around the tiles reached
+        let lights = &world.resource::<LightMap>().lights;
+        assert!(lights.contains_key(&Position::new(2, 0, -1)));
+        assert!(!lights.contains_key(&Position::new(2, 1, 1)));
+
+        // A wall built between them takes the light away from the tiles behind it
+        let wall = Position::new(2, 0, 0);
+        world
+            .resource_mut::<CurrentMap>()
+            .tile_types
+            .insert(wall, TileType::Block);
+        let entity = world.spawn((Tile {}, wall)).id();
+        schedule.run(&mut world);
+        assert!(!world.resource::<LightMap>().lights.contains_key(&behind));
+
+        // And gives it back once dug out
+        world
+            .resource_mut::<CurrentMap>()
+            .tile_types
+            .insert(wall, TileType::Floor);
+        world.despawn(entity);
+        schedule.run(&mut world);
+        assert!(world.resource::<LightMap>().lights.contains_key(&behind));
+    }
+}
@@ -77,9 +77,7 @@ pub fn move_system(
         }
 
         // Update the entity position in the Current Map
-        if grid.entities.remove(&*mob_pos).is_some() {
-            grid.entities.insert(next_step, entity);
-        } else {
+        if !grid.entities.relocate(&mob_pos, next_step, entity) {
             warn!("Tried to move non-existent entity at {:?}", mob_pos);
             continue;
         }
@@ -97,7 +95,7 @@ fn find_random_valid_move(grid: &CurrentMap, mob_pos: &Position) -> Option<Posit
     let neighbors = mob_pos.all_neighbors();
     let valid_moves: Vec<Position> = neighbors
         .into_iter()
-        .filter(|pos| grid.tiles.contains_key(pos)) // Check if the tile exists
+        .filter(|pos| grid.tile_types.contains_key(pos)) // Check if the tile exists
         .filter(|pos| find_path(mob_pos, pos, grid).is_some()) // Check if a path exists
         .collect();
 
@@ -7,15 +7,13 @@ pub fn position_check_system(
     curren_map: Res<CurrentMap>,
 ) {
     for (entity, name, pos) in mob_query.iter() {
-        if let Some(item_entity) = curren_map.items.get(pos) {
+        for item_entity in curren_map.items.get(pos) {
             info!("Uh! {} have found a goodie", name);
             pick_up_item_event.write(Effect::<PickUpItem> {
                 data: PickUpItem {},
                 creator: Some(entity),
                 targets: Targets::Single { target: *item_entity },
             });
-        } else {
-            continue;
-        };
+        }
     }
 }
@@ -7,18 +7,14 @@ pub fn visibility_system(
     grid: Res<CurrentMap>,
 ) {
     for (entity, pos, viewshed) in query.iter_mut() {
-        // Better check every entity if is in the viewshed
-        // OR
-        // check every viewshed position if there is entity collapsing
-        // Check if there is a colliding keys in hashmaps
-        // TODO improve this
-        for (other_pos, other_entity) in grid.entities.iter() {
-            if viewshed.visible_tiles.contains(other_pos) && other_pos != pos {
+        // Only the entities in the range of the viewshed can be seen
+        for (other_pos, other_entity) in grid.entities.in_range(*pos, viewshed.range) {
+            if viewshed.visible_tiles.contains(&other_pos) && other_entity != entity {
                 // TODO in the future this should check factions and those things
                 chase_entity_event.write(Effect::<Chase> {
                     data: Chase {},
                     creator: Some(entity),
-                    targets: Targets::Single { target: *other_entity },
+                    targets: Targets::Single { target: other_entity },
                 });
             }
         }